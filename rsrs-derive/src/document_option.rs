@@ -0,0 +1,246 @@
+use quote::quote;
+use syn::{parse::Parse, Error, LitFloat, LitInt, LitStr, Token};
+
+#[derive(Default, Debug)]
+pub struct DocumentOption {
+    pub index: Option<String>,
+    pub on: Option<String>,
+    pub prefix: Vec<String>,
+    /// The expression, with the span of its literal for errors.
+    pub filter: Option<(String, proc_macro2::Span)>,
+    /// The `::rsrs::Language` variant, spanned at the literal so that an
+    /// unknown language is reported there.
+    pub language: Option<syn::Ident>,
+    pub language_field: Option<String>,
+    pub score: Option<f32>,
+    pub score_field: Option<String>,
+    pub payload_field: Option<String>,
+    pub max_text_fields: bool,
+    pub temporary: Option<usize>,
+    pub no_offsets: bool,
+    pub no_hl: bool,
+    pub no_fields: bool,
+    pub no_freqs: bool,
+    pub stopwords: Option<Vec<String>>,
+    pub skip_initial_scan: bool,
+    pub decode: bool,
+}
+
+impl DocumentOption {
+    /// Checks that the FILTER only references `fields` or the special
+    /// `@__key` and `@__score` properties.
+    pub fn validate_filter(&self, fields: &[String]) -> syn::Result<()> {
+        let Some((filter, span)) = &self.filter else {
+            return Ok(());
+        };
+        let mut chars = filter.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' | '\'' => loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            chars.next();
+                        }
+                        Some(x) if x == c => break,
+                        Some(_) => {}
+                        None => break,
+                    }
+                },
+                '@' => {
+                    let mut name = String::new();
+                    while let Some(x) = chars.next_if(|x| x.is_alphanumeric() || *x == '_') {
+                        name.push(x);
+                    }
+                    if !name.is_empty()
+                        && !matches!(name.as_str(), "__key" | "__score")
+                        && !fields.contains(&name)
+                    {
+                        return Err(Error::new(
+                            *span,
+                            format!("unknown property '@{}' in filter", name),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The key prefix documents are saved under, the first PREFIX.
+    pub fn key_prefix(&self) -> Option<&String> {
+        self.prefix.first()
+    }
+
+    pub fn as_index_option_tokens(&self) -> Vec<proc_macro2::TokenStream> {
+        let mut ret = vec![];
+        match self.on.as_deref() {
+            Some("json") => ret.push(quote! {on: Some(::rsrs::DataType::Json)}),
+            Some(_) => ret.push(quote! {on: Some(::rsrs::DataType::Hash)}),
+            None => {}
+        }
+        if !self.prefix.is_empty() {
+            let prefix = &self.prefix;
+            ret.push(quote! {prefix: Some(vec![#(#prefix.to_string()),*])})
+        }
+        if let Some((filter, _)) = &self.filter {
+            ret.push(quote! {filter: Some(::rsrs::aggregate::ApplyExpr::raw(#filter))})
+        }
+        if let Some(language) = &self.language {
+            ret.push(quote! {language: Some(::rsrs::Language::#language)})
+        }
+        if let Some(language_field) = &self.language_field {
+            ret.push(quote! {language_field: Some(#language_field.to_string())})
+        }
+        if let Some(score) = self.score {
+            ret.push(quote! {score: Some(#score)})
+        }
+        if let Some(score_field) = &self.score_field {
+            ret.push(quote! {score_field: Some(#score_field.to_string())})
+        }
+        if let Some(payload_field) = &self.payload_field {
+            ret.push(quote! {payload_field: Some(#payload_field.to_string())})
+        }
+        if self.max_text_fields {
+            ret.push(quote! {max_text_fields: Some(true)})
+        }
+        if let Some(temporary) = self.temporary {
+            ret.push(quote! {temporary: Some(#temporary)})
+        }
+        if self.no_offsets {
+            ret.push(quote! {no_offsets: Some(true)})
+        }
+        if self.no_hl {
+            ret.push(quote! {no_hl: Some(true)})
+        }
+        if self.no_fields {
+            ret.push(quote! {no_fields: Some(true)})
+        }
+        if self.no_freqs {
+            ret.push(quote! {no_freqs: Some(true)})
+        }
+        if let Some(stopwords) = &self.stopwords {
+            ret.push(quote! {stopwords: Some(vec![#(#stopwords.to_string()),*])})
+        }
+        if self.skip_initial_scan {
+            ret.push(quote! {skip_initial_scan: Some(true)})
+        }
+        ret
+    }
+}
+
+impl Parse for DocumentOption {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut option = DocumentOption::default();
+        loop {
+            let ident: syn::Ident = input.parse()?;
+            match ident.to_string().as_str() {
+                "index" => {
+                    let _: Token![=] = input.parse()?;
+                    let index: LitStr = input.parse()?;
+                    option.index = Some(index.value())
+                }
+                "on" => {
+                    let _: Token![=] = input.parse()?;
+                    let on: syn::Ident = input.parse()?;
+                    option.on = match on.to_string().to_lowercase().as_str() {
+                        "hash" => Some("hash".to_string()),
+                        "json" => Some("json".to_string()),
+                        other => {
+                            return syn::Result::Err(Error::new(
+                                on.span(),
+                                format!("unexpected data type {}", other),
+                            ))
+                        }
+                    }
+                }
+                "prefix" => {
+                    let _: Token![=] = input.parse()?;
+                    option.prefix = parse_str_list(input)?;
+                }
+                "stopwords" => {
+                    let _: Token![=] = input.parse()?;
+                    option.stopwords = Some(parse_str_list(input)?);
+                }
+                "language_field" => {
+                    let _: Token![=] = input.parse()?;
+                    let language_field: LitStr = input.parse()?;
+                    option.language_field = Some(language_field.value())
+                }
+                "score_field" => {
+                    let _: Token![=] = input.parse()?;
+                    let score_field: LitStr = input.parse()?;
+                    option.score_field = Some(score_field.value())
+                }
+                "payload_field" => {
+                    let _: Token![=] = input.parse()?;
+                    let payload_field: LitStr = input.parse()?;
+                    option.payload_field = Some(payload_field.value())
+                }
+                "temporary" => {
+                    let _: Token![=] = input.parse()?;
+                    let temporary: LitInt = input.parse()?;
+                    option.temporary = Some(temporary.base10_parse()?)
+                }
+                "max_text_fields" => option.max_text_fields = true,
+                "no_offsets" => option.no_offsets = true,
+                "no_hl" => option.no_hl = true,
+                "no_fields" => option.no_fields = true,
+                "no_freqs" => option.no_freqs = true,
+                "skip_initial_scan" => option.skip_initial_scan = true,
+                "decode" => option.decode = true,
+                "filter" => {
+                    let _: Token![=] = input.parse()?;
+                    let filter: LitStr = input.parse()?;
+                    option.filter = Some((filter.value(), filter.span()))
+                }
+                "language" => {
+                    let _: Token![=] = input.parse()?;
+                    let language: LitStr = input.parse()?;
+                    let mut variant = language.value().to_lowercase();
+                    if variant.is_empty() || !variant.bytes().all(|b| b.is_ascii_lowercase()) {
+                        return syn::Result::Err(Error::new(
+                            language.span(),
+                            format!("unknown language '{}'", language.value()),
+                        ));
+                    }
+                    variant[..1].make_ascii_uppercase();
+                    option.language = Some(syn::Ident::new(&variant, language.span()))
+                }
+                "score" => {
+                    let _: Token![=] = input.parse()?;
+                    option.score = Some(if input.peek(LitInt) {
+                        input.parse::<LitInt>()?.base10_parse()?
+                    } else {
+                        input.parse::<LitFloat>()?.base10_parse()?
+                    })
+                }
+                other => {
+                    return syn::Result::Err(Error::new(
+                        ident.span(),
+                        format!("unexpected attribute {}", other),
+                    ))
+                }
+            }
+            if input.is_empty() {
+                break;
+            }
+            let _: Token![,] = input.parse()?;
+        }
+
+        Ok(option)
+    }
+}
+
+/// Parses either `"a"` or `["a", "b"]`.
+fn parse_str_list(input: syn::parse::ParseStream) -> syn::Result<Vec<String>> {
+    if input.peek(syn::token::Bracket) {
+        let content;
+        syn::bracketed!(content in input);
+        let list = content.parse_terminated::<LitStr, Token![,]>(|x| x.parse())?;
+        Ok(list.iter().map(|x| x.value()).collect())
+    } else {
+        let lit: LitStr = input.parse()?;
+        Ok(vec![lit.value()])
+    }
+}
@@ -43,3 +43,58 @@ impl Parse for GeoOption {
         Ok(option)
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+enum CoordSystem {
+    Flat,
+    Spherical,
+}
+
+#[derive(Default, Debug)]
+pub struct GeoShapeOption {
+    coord_system: Option<CoordSystem>,
+    no_index: Option<bool>,
+}
+
+impl GeoShapeOption {
+    pub fn as_field_constructor_tokens(&self) -> Vec<proc_macro2::TokenStream> {
+        let mut ret = vec![];
+        match self.coord_system {
+            Some(CoordSystem::Flat) => {
+                ret.push(quote! {coord_system: Some(::rsrs::CoordSystem::Flat)})
+            }
+            Some(CoordSystem::Spherical) => {
+                ret.push(quote! {coord_system: Some(::rsrs::CoordSystem::Spherical)})
+            }
+            None => ret.push(quote! {coord_system: None}),
+        }
+        match self.no_index {
+            Some(i) => ret.push(quote! {no_index: Some(#i)}),
+            None => ret.push(quote! {no_index: None}),
+        }
+        ret
+    }
+}
+
+impl Parse for GeoShapeOption {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut option = GeoShapeOption::default();
+        while input.peek(Token![,]) {
+            let _: Token![,] = input.parse()?;
+            let ident: syn::Ident = input.parse()?;
+            match ident.to_string().to_lowercase().as_str() {
+                "flat" => option.coord_system = Some(CoordSystem::Flat),
+                "spherical" => option.coord_system = Some(CoordSystem::Spherical),
+                "no_index" => option.no_index = Some(true),
+                other => {
+                    return syn::Result::Err(Error::new(
+                        input.span(),
+                        format!("unexpected attribute {}", other),
+                    ))
+                }
+            }
+        }
+
+        Ok(option)
+    }
+}
@@ -1,10 +1,14 @@
+#![allow(clippy::upper_case_acronyms)]
+
+mod document_option;
 mod geo_field;
 mod number_field;
 mod tag_field;
 mod text_field;
 mod vector_field;
 
-use geo_field::GeoOption;
+use document_option::DocumentOption;
+use geo_field::{GeoOption, GeoShapeOption};
 use number_field::NumberOption;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote, ToTokens};
@@ -27,6 +31,7 @@ impl Field {
             FieldType::Tag { option } => option.as_field_constructor_tokens(),
             FieldType::Vector { option } => option.as_field_constructor_tokens(),
             FieldType::Geo { option } => option.as_field_constructor_tokens(),
+            FieldType::GeoShape { option } => option.as_field_constructor_tokens(),
             FieldType::Number { option } => option.as_field_constructor_tokens(),
         }
     }
@@ -38,6 +43,7 @@ enum FieldType {
     Tag { option: TagOption },
     Vector { option: VectorOption },
     Geo { option: GeoOption },
+    GeoShape { option: GeoShapeOption },
     Number { option: NumberOption },
 }
 
@@ -57,6 +63,9 @@ impl Parse for FieldType {
             "geo" => FieldType::Geo {
                 option: GeoOption::parse(input)?,
             },
+            "geoshape" => FieldType::GeoShape {
+                option: GeoShapeOption::parse(input)?,
+            },
             "num" => FieldType::Number {
                 option: NumberOption::parse(input)?,
             },
@@ -99,9 +108,10 @@ fn get_field(ty: &FieldType, field_ty: &str) -> &'static str {
         FieldType::Tag { option: _ } => "TagField",
         FieldType::Vector { option } => match option.datatype.unwrap() {
             VectorDataType::F32 => "VectorFieldF32",
-            VectorDataType::F64 => "VectorFieldF32",
+            VectorDataType::F64 => "VectorFieldF64",
         },
         FieldType::Geo { option: _ } => "GeoField",
+        FieldType::GeoShape { option: _ } => "GeoShapeField",
         FieldType::Number { option: _ } => match field_ty {
             "i8" => "NumberFieldI8",
             "i16" => "NumberFieldI16",
@@ -122,7 +132,7 @@ fn get_field(ty: &FieldType, field_ty: &str) -> &'static str {
     }
 }
 
-#[proc_macro_derive(Document, attributes(field))]
+#[proc_macro_derive(Document, attributes(document, field, id))]
 pub fn document(_input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(_input as DeriveInput);
 
@@ -149,12 +159,38 @@ pub fn document(_input: TokenStream) -> TokenStream {
         }
     };
 
+    let mut option = DocumentOption::default();
+    for attr in &input.attrs {
+        if attr.path.is_ident("document") {
+            option = match attr.parse_args() {
+                Ok(ok) => ok,
+                Err(err) => return TokenStream::from(err.to_compile_error()),
+            };
+        }
+    }
+
     let mut op_fields: Vec<Field> = vec![];
+    let mut other_fields: Vec<syn::Ident> = vec![];
+    let mut id_field: Option<syn::Ident> = None;
 
     for field in fields.named.iter() {
+        if field.attrs.iter().any(|attr| attr.path.is_ident("id")) {
+            if id_field.is_some() {
+                return TokenStream::from(
+                    syn::Error::new(
+                        field.ident.as_ref().unwrap().span(),
+                        "duplicated `#[id]` field",
+                    )
+                    .to_compile_error(),
+                );
+            }
+            id_field = field.ident.clone();
+        } else if !field.attrs.iter().any(|attr| attr.path.is_ident("field")) {
+            other_fields.push(field.ident.clone().unwrap());
+        }
         for attr in &field.attrs {
             if !attr.path.is_ident("field") {
-                break;
+                continue;
             }
             let f: FieldAttr = match attr.parse_args() {
                 Ok(ok) => ok,
@@ -168,6 +204,14 @@ pub fn document(_input: TokenStream) -> TokenStream {
         }
     }
 
+    let field_names = op_fields
+        .iter()
+        .map(|f| f.name.to_string())
+        .collect::<Vec<_>>();
+    if let Err(err) = option.validate_filter(&field_names) {
+        return TokenStream::from(err.to_compile_error());
+    }
+
     let op_struct_fields = op_fields.iter().map(|f| {
         let name = &f.name;
         let ty = format_ident!("{}", get_field(&f.attr.ty, &f.ty));
@@ -192,13 +236,120 @@ pub fn document(_input: TokenStream) -> TokenStream {
     let schema_fields = op_fields.iter().map(|f| {
         let name = &f.name;
         quote! {
-            schema.push(op.#name.to_schema_fields());
+            schema.push(::rsrs::Field::to_schema_fields(&op.#name));
         }
     });
 
-    let output = quote! {
-        use ::rsrs::*;
+    let prefix = option.key_prefix().cloned().unwrap_or_default();
+
+    let decode_fields = op_fields
+        .iter()
+        .filter(|f| Some(&f.name) != id_field.as_ref())
+        .map(|f| {
+            let name = &f.name;
+            let name_str = name.to_string();
+            quote! {
+                #name: ::rsrs::decode_field(&mut fields, #name_str)?
+            }
+        });
+
+    let decode_id = id_field.iter().map(|name| {
+        let name_str = name.to_string();
+        quote! {
+            #name: ::rsrs::decode_id(key, #prefix, #name_str)?
+        }
+    });
+
+    let index_name = option
+        .index
+        .clone()
+        .unwrap_or_else(|| name.to_string().to_lowercase());
+    let index_option = option.as_index_option_tokens();
+
+    let key_prefix = match option.key_prefix() {
+        Some(prefix) => quote! {
+            fn key_prefix() -> Option<&'static str> {
+                Some(#prefix)
+            }
+        },
+        None => quote! {},
+    };
+
+    let key = match &id_field {
+        Some(id) => quote! {
+            fn key(&self) -> Option<String> {
+                Some(format!("{}{}", #prefix, self.#id))
+            }
+        },
+        None => quote! {},
+    };
+
+    let encode_fields = op_fields.iter().map(|f| {
+        let name = &f.name;
+        let name_str = name.to_string();
+        quote! {
+            (#name_str, &self.#name as &dyn ::rsrs::EncodeField)
+        }
+    });
+
+    let decode_json_id = id_field.iter().map(|name| {
+        let name_str = name.to_string();
+        quote! {
+            doc.#name = ::rsrs::decode_id(key, #prefix, #name_str)?;
+        }
+    });
+
+    // Every member without `#[field]` or `#[id]` is filled with its Default,
+    // so decoding is only derived on request. JSON documents are decoded
+    // through serde, their id still read from the key.
+    let decode_document = if option.decode && option.on.as_deref() == Some("json") {
+        quote! {
+            impl ::rsrs::DecodeDocument for #name {
+                fn decode_document(
+                    key: &str,
+                    fields: Vec<(String, ::rsrs::redis::Value)>,
+                ) -> ::rsrs::Result<Self> {
+                    Self::decode_returned(key, fields, &[])
+                }
+
+                fn decode_returned(
+                    #[allow(unused_variables)] key: &str,
+                    fields: Vec<(String, ::rsrs::redis::Value)>,
+                    returns: &[::rsrs::query::FTSearchReturn],
+                ) -> ::rsrs::Result<Self> {
+                    #[allow(unused_mut)]
+                    let mut doc = <::rsrs::json::Json<Self> as ::rsrs::DecodeDocument>::decode_returned(
+                        key, fields, returns,
+                    )?
+                    .into_inner();
+                    #(#decode_json_id)*
+                    Ok(doc)
+                }
+            }
+        }
+    } else if option.decode {
+        quote! {
+            impl ::rsrs::DecodeDocument for #name {
+                fn decode_document(
+                    #[allow(unused_variables)] key: &str,
+                    fields: Vec<(String, ::rsrs::redis::Value)>,
+                ) -> ::rsrs::Result<Self> {
+                    let mut fields: ::std::collections::HashMap<String, ::rsrs::redis::Value> =
+                        fields.into_iter().collect();
+                    Ok(Self {
+                        #(#decode_id,)*
+                        #(#decode_fields,)*
+                        #(#other_fields: Default::default(),)*
+                    })
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
 
+    let output = quote! {
+        #[allow(non_camel_case_types)]
         struct #op_name {
             #(#op_struct_fields),*
         }
@@ -218,10 +369,28 @@ pub fn document(_input: TokenStream) -> TokenStream {
                 Default::default()
             }
 
-            fn create_index(index: &str, option: IndexOption) -> FTCreate {
+            #key_prefix
+
+            #key
+
+            fn index_name() -> &'static str {
+                #index_name
+            }
+
+            fn index_option() -> ::rsrs::IndexOption {
+                ::rsrs::IndexOption {
+                    #(#index_option,)*
+                    ..::std::default::Default::default()
+                }
+            }
+
+            fn create_index(index: &str, option: ::rsrs::IndexOption) -> ::rsrs::FTCreate {
+                let option = option
+                    .or(<Self as ::rsrs::Document>::index_option())
+                    .with_key_prefix(<Self as ::rsrs::Document>::key_prefix());
                 let mut schema = vec![];
 
-                let op = Self::op();
+                let op = <Self as ::rsrs::Document>::op();
                 #(#schema_fields)*
 
                 ::rsrs::FTCreate::new (
@@ -230,7 +399,13 @@ pub fn document(_input: TokenStream) -> TokenStream {
                     schema,
                 )
             }
+
+            fn fields(&self) -> Vec<(&'static str, &dyn ::rsrs::EncodeField)> {
+                vec![#(#encode_fields),*]
+            }
         }
+
+        #decode_document
     };
     output.into()
 }
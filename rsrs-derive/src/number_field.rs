@@ -4,7 +4,6 @@ use syn::{parse::Parse, Error, Token};
 #[derive(Default, Debug)]
 pub struct NumberOption {
     sortable: Option<bool>,
-    unf: Option<bool>,
     no_index: Option<bool>,
 }
 
@@ -13,8 +12,6 @@ impl NumberOption {
         let mut ret = vec![];
         let sortable = self.sortable.unwrap_or_default();
         ret.push(quote! {sortable: #sortable});
-        let unf = self.unf.unwrap_or_default();
-        ret.push(quote! {unf: #unf});
         let no_index = self.no_index.unwrap_or_default();
         ret.push(quote! {no_index: #no_index});
         ret
@@ -29,7 +26,6 @@ impl Parse for NumberOption {
             let ident: syn::Ident = input.parse()?;
             match ident.to_string().as_str() {
                 "sortable" => option.sortable = Some(true),
-                "unf" => option.unf = Some(true),
                 "no_index" => option.no_index = Some(true),
                 other => {
                     return syn::Result::Err(Error::new(
@@ -3,8 +3,14 @@ use syn::{parse::Parse, Error, LitStr, Token};
 
 #[derive(Default, Debug)]
 pub struct TagOption {
-    separator: Option<String>,
+    separator: Option<char>,
     case_sensitive: Option<bool>,
+    with_suffix_trie: Option<bool>,
+    index_empty: Option<bool>,
+    index_missing: Option<bool>,
+    sortable: Option<bool>,
+    unf: Option<bool>,
+    no_index: Option<bool>,
 }
 
 impl TagOption {
@@ -18,6 +24,30 @@ impl TagOption {
             Some(i) => ret.push(quote! {case_sensitive: Some(#i)}),
             None => ret.push(quote! {case_sensitive: None}),
         }
+        match self.with_suffix_trie {
+            Some(i) => ret.push(quote! {with_suffix_trie: Some(#i)}),
+            None => ret.push(quote! {with_suffix_trie: None}),
+        }
+        match self.index_empty {
+            Some(i) => ret.push(quote! {index_empty: Some(#i)}),
+            None => ret.push(quote! {index_empty: None}),
+        }
+        match self.index_missing {
+            Some(i) => ret.push(quote! {index_missing: Some(#i)}),
+            None => ret.push(quote! {index_missing: None}),
+        }
+        match self.sortable {
+            Some(i) => ret.push(quote! {sortable: Some(#i)}),
+            None => ret.push(quote! {sortable: None}),
+        }
+        match self.unf {
+            Some(i) => ret.push(quote! {unf: Some(#i)}),
+            None => ret.push(quote! {unf: None}),
+        }
+        match self.no_index {
+            Some(i) => ret.push(quote! {no_index: Some(#i)}),
+            None => ret.push(quote! {no_index: None}),
+        }
         ret
     }
 }
@@ -31,10 +61,26 @@ impl Parse for TagOption {
             match ident.to_string().to_lowercase().as_str() {
                 "separator" => {
                     let _: Token![=] = input.parse()?;
-                    let weight: LitStr = input.parse()?;
-                    option.separator = Some(weight.value())
+                    let separator: LitStr = input.parse()?;
+                    let value = separator.value();
+                    let mut chars = value.chars();
+                    option.separator = match (chars.next(), chars.next()) {
+                        (Some(c), None) => Some(c),
+                        _ => {
+                            return syn::Result::Err(Error::new(
+                                separator.span(),
+                                "separator must be a single character",
+                            ))
+                        }
+                    }
                 }
                 "case_sensitive" => option.case_sensitive = Some(true),
+                "with_suffix_trie" => option.with_suffix_trie = Some(true),
+                "index_empty" => option.index_empty = Some(true),
+                "index_missing" => option.index_missing = Some(true),
+                "sortable" => option.sortable = Some(true),
+                "unf" => option.unf = Some(true),
+                "no_index" => option.no_index = Some(true),
                 other => {
                     return syn::Result::Err(Error::new(
                         input.span(),
@@ -43,6 +89,9 @@ impl Parse for TagOption {
                 }
             }
         }
+        if option.unf.is_some() && option.sortable.is_none() {
+            return syn::Result::Err(Error::new(input.span(), "unf requires sortable"));
+        }
 
         Ok(option)
     }
@@ -7,6 +7,7 @@ pub struct TextOption {
     no_stem: Option<bool>,
     phonetic: Option<PhoneticMatcher>,
     sortable: Option<bool>,
+    unf: Option<bool>,
     no_index: Option<bool>,
     with_suffix_trie: Option<bool>,
 }
@@ -41,6 +42,10 @@ impl TextOption {
             Some(i) => ret.push(quote! {sortable: Some(#i)}),
             None => ret.push(quote! {sortable: None}),
         }
+        match self.unf {
+            Some(i) => ret.push(quote! {unf: Some(#i)}),
+            None => ret.push(quote! {unf: None}),
+        }
         match self.no_index {
             Some(i) => ret.push(quote! {no_index: Some(#i)}),
             None => ret.push(quote! {no_index: None}),
@@ -67,6 +72,7 @@ impl Parse for TextOption {
                 }
                 "no_stem" => option.no_stem = Some(true),
                 "sortable" => option.sortable = Some(true),
+                "unf" => option.unf = Some(true),
                 "no_index" => option.no_index = Some(true),
                 "with_suffix_trie" => option.with_suffix_trie = Some(true),
                 "phonetic" => {
@@ -95,6 +101,10 @@ impl Parse for TextOption {
             }
         }
 
+        if option.unf.is_some() && option.sortable.is_none() {
+            return syn::Result::Err(Error::new(input.span(), "unf requires sortable"));
+        }
+
         Ok(option)
     }
 }
@@ -1,5 +1,5 @@
 use quote::quote;
-use syn::{parse::Parse, Error, LitInt, Token};
+use syn::{parse::Parse, Error, LitFloat, LitInt, Token};
 
 #[derive(Debug, Clone, Copy)]
 enum VectorAlgorithm {
@@ -31,7 +31,7 @@ pub struct VectorOption {
     m: Option<usize>,
     ef_construction: Option<usize>,
     ef_runtime: Option<usize>,
-    epsilon: Option<usize>,
+    epsilon: Option<f32>,
 }
 
 impl VectorOption {
@@ -108,7 +108,7 @@ impl Parse for VectorOption {
                 }
                 "epsilon" => {
                     let _: Token![=] = input.parse()?;
-                    let epsilon: LitInt = input.parse()?;
+                    let epsilon: LitFloat = input.parse()?;
                     option.epsilon = Some(epsilon.base10_parse()?)
                 }
                 "flat" => option.algorithm = Some(VectorAlgorithm::FLAT),
@@ -151,21 +151,35 @@ impl Parse for VectorOption {
             }
         }
         if option.algorithm.is_none() {
-            return syn::Result::Err(Error::new(
-                input.span(),
-                format!("missing attribute: hnsw or flat"),
-            ));
+            return syn::Result::Err(Error::new(input.span(), "missing attribute: hnsw or flat"));
+        }
+        match option.algorithm {
+            Some(VectorAlgorithm::FLAT)
+                if option.m.is_some()
+                    || option.ef_construction.is_some()
+                    || option.ef_runtime.is_some()
+                    || option.epsilon.is_some() =>
+            {
+                return syn::Result::Err(Error::new(
+                    input.span(),
+                    "m, ef_construction, ef_runtime and epsilon are only valid with hnsw",
+                ));
+            }
+            Some(VectorAlgorithm::HNSW) if option.block_size.is_some() => {
+                return syn::Result::Err(Error::new(
+                    input.span(),
+                    "block_size is only valid with flat",
+                ));
+            }
+            _ => {}
         }
         if option.datatype.is_none() {
-            return syn::Result::Err(Error::new(
-                input.span(),
-                format!("missing attribute: f32 or f64"),
-            ));
+            return syn::Result::Err(Error::new(input.span(), "missing attribute: f32 or f64"));
         }
         if option.distance_metric.is_none() {
             return syn::Result::Err(Error::new(
                 input.span(),
-                format!("missing attribute: distance_metric"),
+                "missing attribute: distance_metric",
             ));
         }
 
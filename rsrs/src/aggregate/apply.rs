@@ -0,0 +1,444 @@
+use std::marker::PhantomData;
+
+use crate::{Field, GeoField, TagField, TextField};
+
+/// Kind of an [`ApplyExpr`] evaluating to a number.
+#[derive(Debug, Clone, Copy)]
+pub struct Num;
+
+/// Kind of an [`ApplyExpr`] evaluating to a string.
+#[derive(Debug, Clone, Copy)]
+pub struct Str;
+
+/// Kind of an [`ApplyExpr`] evaluating to a condition, as used by FILTER.
+#[derive(Debug, Clone, Copy)]
+pub struct Bool;
+
+/// Kind of an [`ApplyExpr`] evaluating to a geo point.
+#[derive(Debug, Clone, Copy)]
+pub struct Geo;
+
+const PREC_OR: u8 = 1;
+const PREC_AND: u8 = 2;
+const PREC_EQ: u8 = 3;
+const PREC_CMP: u8 = 4;
+const PREC_ADD: u8 = 5;
+const PREC_MUL: u8 = 6;
+const PREC_POW: u8 = 7;
+const PREC_UNARY: u8 = 8;
+const PREC_ATOM: u8 = 9;
+
+/// An expression of RediSearch's aggregation expression language, used by
+/// APPLY and FILTER. `T` is the kind of value it evaluates to.
+#[derive(Debug, Clone)]
+pub struct ApplyExpr<T> {
+    text: String,
+    precedence: u8,
+    _kind: PhantomData<T>,
+}
+
+impl<T> ApplyExpr<T> {
+    fn new(text: String, precedence: u8) -> Self {
+        ApplyExpr {
+            text,
+            precedence,
+            _kind: PhantomData,
+        }
+    }
+
+    fn call(name: &str, args: &[&str]) -> Self {
+        ApplyExpr::new(format!("{}({})", name, args.join(", ")), PREC_ATOM)
+    }
+
+    /// Wraps already written expression text, skipping all type checks.
+    pub fn raw(text: &str) -> Self {
+        ApplyExpr::new(text.to_string(), PREC_OR - 1)
+    }
+
+    /// References a property by name, e.g. an alias created by REDUCE or APPLY.
+    pub fn property(name: &str) -> Self {
+        ApplyExpr::new(format!("@{}", name), PREC_ATOM)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Lists the properties the expression references, checking that string
+    /// literals are closed, parentheses balanced and every bare word is a
+    /// function call.
+    pub(crate) fn properties(&self) -> Result<Vec<String>, String> {
+        let mut ret = vec![];
+        let mut depth = 0;
+        let mut chars = self.text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' | '\'' => loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            chars.next();
+                        }
+                        Some(x) if x == c => break,
+                        Some(_) => {}
+                        None => return Err("unterminated string literal".to_string()),
+                    }
+                },
+                '(' => depth += 1,
+                ')' if depth == 0 => return Err("unbalanced parentheses".to_string()),
+                ')' => depth -= 1,
+                c if c.is_ascii_digit() || c == '.' => {
+                    // Exponents and suffixes of the number, e.g. `1e-5`.
+                    while chars
+                        .next_if(|x| x.is_alphanumeric() || *x == '.' || *x == '_')
+                        .is_some()
+                    {}
+                }
+                c if c.is_alphabetic() || c == '_' => {
+                    let mut word = c.to_string();
+                    while let Some(x) = chars.next_if(|x| x.is_alphanumeric() || *x == '_') {
+                        word.push(x);
+                    }
+                    while chars.next_if(|x| x.is_whitespace()).is_some() {}
+                    if chars.peek() != Some(&'(') {
+                        return Err(format!("unexpected word `{}`", word));
+                    }
+                }
+                '@' => {
+                    let mut name = String::new();
+                    while let Some(x) = chars.next_if(|x| x.is_alphanumeric() || *x == '_') {
+                        name.push(x);
+                    }
+                    if name.is_empty() {
+                        return Err("`@` without a property name".to_string());
+                    }
+                    ret.push(name);
+                }
+                _ => {}
+            }
+        }
+        if depth != 0 {
+            return Err("unbalanced parentheses".to_string());
+        }
+        Ok(ret)
+    }
+
+    fn operand(&self, precedence: u8) -> String {
+        if self.precedence < precedence {
+            format!("({})", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+
+    fn binary<U, R>(self, op: &str, precedence: u8, rhs: ApplyExpr<R>) -> ApplyExpr<U> {
+        ApplyExpr::new(
+            format!(
+                "{} {} {}",
+                self.operand(precedence),
+                op,
+                rhs.operand(precedence + 1)
+            ),
+            precedence,
+        )
+    }
+
+    pub fn eq(self, other: impl Into<ApplyExpr<T>>) -> ApplyExpr<Bool> {
+        self.binary("==", PREC_EQ, other.into())
+    }
+
+    pub fn ne(self, other: impl Into<ApplyExpr<T>>) -> ApplyExpr<Bool> {
+        self.binary("!=", PREC_EQ, other.into())
+    }
+
+    pub fn lt(self, other: impl Into<ApplyExpr<T>>) -> ApplyExpr<Bool> {
+        self.binary("<", PREC_CMP, other.into())
+    }
+
+    pub fn le(self, other: impl Into<ApplyExpr<T>>) -> ApplyExpr<Bool> {
+        self.binary("<=", PREC_CMP, other.into())
+    }
+
+    pub fn gt(self, other: impl Into<ApplyExpr<T>>) -> ApplyExpr<Bool> {
+        self.binary(">", PREC_CMP, other.into())
+    }
+
+    pub fn ge(self, other: impl Into<ApplyExpr<T>>) -> ApplyExpr<Bool> {
+        self.binary(">=", PREC_CMP, other.into())
+    }
+}
+
+impl ApplyExpr<Num> {
+    pub fn pow(self, exponent: impl Into<ApplyExpr<Num>>) -> ApplyExpr<Num> {
+        // `^` is right associative, and binds tighter than a unary minus.
+        let exponent = exponent.into();
+        ApplyExpr::new(
+            format!(
+                "{} ^ {}",
+                self.operand(PREC_ATOM),
+                exponent.operand(PREC_POW)
+            ),
+            PREC_POW,
+        )
+    }
+}
+
+impl ApplyExpr<Num> {
+    /// The score of the document, `@__score`.
+    pub fn score() -> ApplyExpr<Num> {
+        ApplyExpr::property("__score")
+    }
+}
+
+impl ApplyExpr<Str> {
+    /// The key of the document, `@__key`.
+    pub fn key() -> ApplyExpr<Str> {
+        ApplyExpr::property("__key")
+    }
+}
+
+impl ApplyExpr<Bool> {
+    pub fn and(self, other: ApplyExpr<Bool>) -> ApplyExpr<Bool> {
+        self.binary("&&", PREC_AND, other)
+    }
+
+    pub fn or(self, other: ApplyExpr<Bool>) -> ApplyExpr<Bool> {
+        self.binary("||", PREC_OR, other)
+    }
+}
+
+impl std::ops::Not for ApplyExpr<Bool> {
+    type Output = ApplyExpr<Bool>;
+
+    fn not(self) -> ApplyExpr<Bool> {
+        ApplyExpr::new(format!("!{}", self.operand(PREC_UNARY)), PREC_UNARY)
+    }
+}
+
+impl std::ops::Neg for ApplyExpr<Num> {
+    type Output = ApplyExpr<Num>;
+
+    fn neg(self) -> ApplyExpr<Num> {
+        ApplyExpr::new(format!("-{}", self.operand(PREC_UNARY)), PREC_UNARY)
+    }
+}
+
+macro_rules! impl_arithmetic {
+    ($trait:ident, $fn:ident, $op:literal, $precedence:ident) => {
+        impl<R: Into<ApplyExpr<Num>>> std::ops::$trait<R> for ApplyExpr<Num> {
+            type Output = ApplyExpr<Num>;
+
+            fn $fn(self, rhs: R) -> ApplyExpr<Num> {
+                self.binary($op, $precedence, rhs.into())
+            }
+        }
+    };
+}
+
+impl_arithmetic!(Add, add, "+", PREC_ADD);
+impl_arithmetic!(Sub, sub, "-", PREC_ADD);
+impl_arithmetic!(Mul, mul, "*", PREC_MUL);
+impl_arithmetic!(Div, div, "/", PREC_MUL);
+impl_arithmetic!(Rem, rem, "%", PREC_MUL);
+
+/// Lets a field reference be used directly as the left operand of arithmetic,
+/// e.g. `&op.price * &op.qty`.
+macro_rules! impl_field_arithmetic {
+    ($ty:ty) => {
+        $crate::aggregate::apply::impl_field_arithmetic!($ty, Add, add);
+        $crate::aggregate::apply::impl_field_arithmetic!($ty, Sub, sub);
+        $crate::aggregate::apply::impl_field_arithmetic!($ty, Mul, mul);
+        $crate::aggregate::apply::impl_field_arithmetic!($ty, Div, div);
+        $crate::aggregate::apply::impl_field_arithmetic!($ty, Rem, rem);
+    };
+    ($ty:ty, $trait:ident, $fn:ident) => {
+        impl<R: Into<$crate::aggregate::apply::ApplyExpr<$crate::aggregate::apply::Num>>>
+            std::ops::$trait<R> for &$ty
+        {
+            type Output = $crate::aggregate::apply::ApplyExpr<$crate::aggregate::apply::Num>;
+
+            fn $fn(self, rhs: R) -> Self::Output {
+                std::ops::$trait::$fn(
+                    $crate::aggregate::apply::ApplyExpr::<$crate::aggregate::apply::Num>::from(
+                        self,
+                    ),
+                    rhs,
+                )
+            }
+        }
+    };
+}
+
+pub(crate) use impl_field_arithmetic;
+
+macro_rules! impl_number_literal {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for ApplyExpr<Num> {
+                /// NaN and infinities have no literal, they are rendered as
+                /// bare words the expression checks reject.
+                fn from(value: $ty) -> Self {
+                    let text = value.to_string();
+                    let precedence = if text.starts_with('-') {
+                        PREC_UNARY
+                    } else {
+                        PREC_ATOM
+                    };
+                    ApplyExpr::new(text, precedence)
+                }
+            }
+        )*
+    };
+}
+
+impl_number_literal!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+impl From<&str> for ApplyExpr<Str> {
+    fn from(value: &str) -> Self {
+        ApplyExpr::new(quote(value), PREC_ATOM)
+    }
+}
+
+impl From<String> for ApplyExpr<Str> {
+    fn from(value: String) -> Self {
+        value.as_str().into()
+    }
+}
+
+impl From<&TextField> for ApplyExpr<Str> {
+    fn from(field: &TextField) -> Self {
+        ApplyExpr::property(field.field_name())
+    }
+}
+
+impl From<&TagField> for ApplyExpr<Str> {
+    fn from(field: &TagField) -> Self {
+        ApplyExpr::property(field.field_name())
+    }
+}
+
+impl From<&GeoField> for ApplyExpr<Geo> {
+    fn from(field: &GeoField) -> Self {
+        ApplyExpr::property(field.field_name())
+    }
+}
+
+impl ApplyExpr<Geo> {
+    pub fn point(lon: f64, lat: f64) -> ApplyExpr<Geo> {
+        ApplyExpr::new(quote(&format!("{},{}", lon, lat)), PREC_ATOM)
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// An argument of `format`, which accepts values of any kind.
+#[derive(Debug, Clone)]
+pub struct ApplyArg(String);
+
+impl<T> From<ApplyExpr<T>> for ApplyArg {
+    fn from(expr: ApplyExpr<T>) -> Self {
+        ApplyArg(expr.text)
+    }
+}
+
+impl<F: Field + ?Sized> From<&F> for ApplyArg {
+    fn from(field: &F) -> Self {
+        ApplyArg(format!("@{}", field.field_name()))
+    }
+}
+
+pub fn exists<F: Field + ?Sized>(field: &F) -> ApplyExpr<Bool> {
+    ApplyExpr::call("exists", &[&format!("@{}", field.field_name())])
+}
+
+pub fn to_number(s: impl Into<ApplyExpr<Str>>) -> ApplyExpr<Num> {
+    ApplyExpr::call("to_number", &[&s.into().text])
+}
+
+pub fn to_str(x: impl Into<ApplyExpr<Num>>) -> ApplyExpr<Str> {
+    ApplyExpr::call("to_str", &[&x.into().text])
+}
+
+macro_rules! impl_unary_fn {
+    ($($name:ident: $from:ident -> $to:ident),* $(,)?) => {
+        $(
+            pub fn $name(x: impl Into<ApplyExpr<$from>>) -> ApplyExpr<$to> {
+                ApplyExpr::call(stringify!($name), &[&x.into().text])
+            }
+        )*
+    };
+}
+
+impl_unary_fn!(
+    log: Num -> Num,
+    abs: Num -> Num,
+    ceil: Num -> Num,
+    floor: Num -> Num,
+    log2: Num -> Num,
+    exp: Num -> Num,
+    sqrt: Num -> Num,
+    upper: Str -> Str,
+    lower: Str -> Str,
+    strlen: Str -> Num,
+    minute: Num -> Num,
+    hour: Num -> Num,
+    day: Num -> Num,
+    month: Num -> Num,
+    dayofweek: Num -> Num,
+    dayofmonth: Num -> Num,
+    dayofyear: Num -> Num,
+    year: Num -> Num,
+    monthofyear: Num -> Num,
+);
+
+pub fn substr(
+    s: impl Into<ApplyExpr<Str>>,
+    offset: impl Into<ApplyExpr<Num>>,
+    count: impl Into<ApplyExpr<Num>>,
+) -> ApplyExpr<Str> {
+    ApplyExpr::call(
+        "substr",
+        &[&s.into().text, &offset.into().text, &count.into().text],
+    )
+}
+
+pub fn startswith(
+    s: impl Into<ApplyExpr<Str>>,
+    prefix: impl Into<ApplyExpr<Str>>,
+) -> ApplyExpr<Bool> {
+    ApplyExpr::call("startswith", &[&s.into().text, &prefix.into().text])
+}
+
+/// Number of occurrences of `substring` in `s`.
+pub fn contains(
+    s: impl Into<ApplyExpr<Str>>,
+    substring: impl Into<ApplyExpr<Str>>,
+) -> ApplyExpr<Num> {
+    ApplyExpr::call("contains", &[&s.into().text, &substring.into().text])
+}
+
+pub fn format(fmt: &str, args: Vec<ApplyArg>) -> ApplyExpr<Str> {
+    let fmt = quote(fmt);
+    let mut call_args = vec![fmt.as_str()];
+    call_args.extend(args.iter().map(|x| x.0.as_str()));
+    ApplyExpr::call("format", &call_args)
+}
+
+/// Formats a unix timestamp, using `%FT%TZ` when no format is given.
+pub fn timefmt(x: impl Into<ApplyExpr<Num>>, fmt: Option<&str>) -> ApplyExpr<Str> {
+    let x = x.into().text;
+    match fmt {
+        Some(fmt) => ApplyExpr::call("timefmt", &[&x, &quote(fmt)]),
+        None => ApplyExpr::call("timefmt", &[&x]),
+    }
+}
+
+pub fn parsetime(s: impl Into<ApplyExpr<Str>>, fmt: &str) -> ApplyExpr<Num> {
+    ApplyExpr::call("parsetime", &[&s.into().text, &quote(fmt)])
+}
+
+/// Distance in meters between two geo points.
+pub fn geodistance(a: impl Into<ApplyExpr<Geo>>, b: impl Into<ApplyExpr<Geo>>) -> ApplyExpr<Num> {
+    ApplyExpr::call("geodistance", &[&a.into().text, &b.into().text])
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::VecDeque,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{future::BoxFuture, Stream};
+use redis::{aio::ConnectionLike, FromRedisValue, RedisResult, Value};
+
+use super::{FTAggregate, WithCursor};
+
+type Request<C> = BoxFuture<'static, (C, RedisResult<Value>)>;
+
+/// A `Stream` of the rows of an FT.AGGREGATE run WITHCURSOR.
+///
+/// Pages are fetched with FT.CURSOR READ until the server returns cursor id 0.
+/// Dropping the stream before that deletes the cursor with FT.CURSOR DEL on
+/// the current tokio runtime, with the `tokio` feature. Without the feature or
+/// a runtime the cursor is left to expire after its MAXIDLE.
+pub struct AggregateCursor<C, T>
+where
+    C: ConnectionLike + Send + 'static,
+{
+    index: String,
+    count: Option<usize>,
+    cursor_id: u64,
+    conn: Option<C>,
+    pending: Option<Request<C>>,
+    rows: VecDeque<Value>,
+    done: bool,
+    _row: PhantomData<fn() -> T>,
+}
+
+// The connection is only ever moved, never pinned.
+impl<C, T> Unpin for AggregateCursor<C, T> where C: ConnectionLike + Send + 'static {}
+
+impl<C, T> AggregateCursor<C, T>
+where
+    C: ConnectionLike + Send + 'static,
+{
+    pub fn new(conn: C, mut aggregate: FTAggregate) -> Self {
+        let cursor = *aggregate
+            .option
+            .cursor
+            .get_or_insert_with(WithCursor::default);
+        let cmd = aggregate.to_cmd();
+
+        AggregateCursor {
+            index: aggregate.index,
+            count: cursor.count,
+            cursor_id: 0,
+            conn: None,
+            pending: Some(request(conn, cmd)),
+            rows: VecDeque::new(),
+            done: false,
+            _row: PhantomData,
+        }
+    }
+
+    pub fn cursor_id(&self) -> u64 {
+        self.cursor_id
+    }
+
+    fn read(&mut self, conn: C) {
+        let mut cmd = redis::cmd("FT.CURSOR");
+        cmd.arg("READ").arg(&self.index).arg(self.cursor_id);
+        if let Some(count) = self.count {
+            cmd.arg("COUNT").arg(count);
+        }
+        self.pending = Some(request(conn, cmd));
+    }
+}
+
+impl<C, T> Stream for AggregateCursor<C, T>
+where
+    C: ConnectionLike + Send + 'static,
+    T: FromRedisValue,
+{
+    type Item = crate::Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(row) = this.rows.pop_front() {
+                return Poll::Ready(Some(T::from_redis_value(&row).map_err(Into::into)));
+            }
+
+            if let Some(pending) = this.pending.as_mut() {
+                let (conn, reply) = match pending.as_mut().poll(cx) {
+                    Poll::Ready(ready) => ready,
+                    Poll::Pending => return Poll::Pending,
+                };
+                this.pending = None;
+                this.conn = Some(conn);
+                match reply.map_err(Into::into).and_then(parse_cursor_reply) {
+                    Ok((cursor_id, rows)) => {
+                        this.cursor_id = cursor_id;
+                        this.rows.extend(rows);
+                    }
+                    Err(err) => {
+                        this.done = true;
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                }
+                continue;
+            }
+
+            if this.done || this.cursor_id == 0 {
+                return Poll::Ready(None);
+            }
+
+            let conn = this
+                .conn
+                .take()
+                .expect("connection is held between requests");
+            this.read(conn);
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<C, T> Drop for AggregateCursor<C, T>
+where
+    C: ConnectionLike + Send + 'static,
+{
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let index = std::mem::take(&mut self.index);
+        let cursor_id = self.cursor_id;
+        let conn = self.conn.take();
+        let pending = self.pending.take();
+        if pending.is_none() && cursor_id == 0 {
+            return;
+        }
+
+        runtime.spawn(async move {
+            let (mut conn, cursor_id) = match (pending, conn) {
+                (Some(pending), _) => {
+                    let (conn, reply) = pending.await;
+                    let cursor_id = reply
+                        .map_err(Into::into)
+                        .and_then(parse_cursor_reply)
+                        .map(|(cursor_id, _)| cursor_id)
+                        .unwrap_or(cursor_id);
+                    (conn, cursor_id)
+                }
+                (None, Some(conn)) => (conn, cursor_id),
+                (None, None) => return,
+            };
+            if cursor_id != 0 {
+                let _: RedisResult<Value> = redis::cmd("FT.CURSOR")
+                    .arg("DEL")
+                    .arg(index)
+                    .arg(cursor_id)
+                    .query_async(&mut conn)
+                    .await;
+            }
+        });
+    }
+}
+
+fn request<C>(mut conn: C, cmd: redis::Cmd) -> Request<C>
+where
+    C: ConnectionLike + Send + 'static,
+{
+    Box::pin(async move {
+        let reply = cmd.query_async(&mut conn).await;
+        (conn, reply)
+    })
+}
+
+/// Splits a `[[total, row, ...], cursor_id]` reply.
+fn parse_cursor_reply(reply: Value) -> crate::Result<(u64, Vec<Value>)> {
+    let Value::Bulk(mut reply) = reply else {
+        return Err(crate::Error::UnexpectedReply);
+    };
+    if reply.len() != 2 {
+        return Err(crate::Error::UnexpectedReply);
+    }
+    let cursor_id = u64::from_redis_value(&reply[1])?;
+    let Value::Bulk(mut rows) = reply.swap_remove(0) else {
+        return Err(crate::Error::UnexpectedReply);
+    };
+    if rows.is_empty() {
+        return Err(crate::Error::UnexpectedReply);
+    }
+    rows.remove(0);
+    Ok((cursor_id, rows))
+}
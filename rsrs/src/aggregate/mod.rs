@@ -0,0 +1,404 @@
+pub mod apply;
+mod cursor;
+mod reducer;
+
+pub use apply::ApplyExpr;
+pub use cursor::*;
+pub use reducer::*;
+
+use redis::{FromRedisValue, ToRedisArgs, Value};
+
+use crate::{
+    query::{FTSearchParam, Limit},
+    Expr, Field, SortOrder,
+};
+
+/// Anything that can be referenced as `@name` in an aggregation pipeline:
+/// schema fields, or aliases created by REDUCE / APPLY.
+pub trait AsProperty {
+    fn property_name(&self) -> &str;
+}
+
+impl<F: Field + ?Sized> AsProperty for F {
+    fn property_name(&self) -> &str {
+        self.field_name()
+    }
+}
+
+impl AsProperty for &str {
+    fn property_name(&self) -> &str {
+        self
+    }
+}
+
+impl AsProperty for String {
+    fn property_name(&self) -> &str {
+        self
+    }
+}
+
+fn property(p: &(impl AsProperty + ?Sized)) -> String {
+    format!("@{}", p.property_name())
+}
+
+#[derive(Debug)]
+pub struct FTAggregate {
+    pub index: String,
+    pub query: String,
+    pub option: FTAggregateOption,
+}
+
+impl FTAggregate {
+    pub fn to_cmd(&self) -> redis::Cmd {
+        let mut cmd = redis::cmd("FT.AGGREGATE");
+        cmd.arg(self);
+        cmd
+    }
+}
+
+/// The rows of an FT.AGGREGATE run without a cursor, see [`AggregateCursor`]
+/// for WITHCURSOR.
+#[derive(Debug, Clone)]
+pub struct AggregateResults<T> {
+    pub total: usize,
+    pub rows: Vec<T>,
+}
+
+impl<T: FromRedisValue> AggregateResults<T> {
+    pub fn from_reply(reply: Value) -> crate::Result<Self> {
+        let Value::Bulk(mut reply) = reply else {
+            return Err(crate::Error::UnexpectedReply);
+        };
+        if reply.is_empty() {
+            return Err(crate::Error::UnexpectedReply);
+        }
+        let total = usize::from_redis_value(&reply.remove(0))?;
+        let rows = reply
+            .iter()
+            .map(T::from_redis_value)
+            .collect::<redis::RedisResult<_>>()?;
+        Ok(AggregateResults { total, rows })
+    }
+}
+
+impl ToRedisArgs for FTAggregate {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        self.index.write_redis_args(out);
+        self.query.write_redis_args(out);
+        self.option.write_redis_args(out);
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct FTAggregateOption {
+    pub verbatim: bool,
+    pub timeout: Option<usize>,
+    pub steps: Vec<AggregateStep>,
+    pub cursor: Option<WithCursor>,
+    pub params: Option<Vec<FTSearchParam>>,
+    pub dialect: Option<usize>,
+}
+
+impl ToRedisArgs for FTAggregateOption {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        if self.verbatim {
+            "VERBATIM".write_redis_args(out);
+        }
+        if let Some(timeout) = self.timeout {
+            "TIMEOUT".write_redis_args(out);
+            timeout.write_redis_args(out);
+        }
+        for step in &self.steps {
+            step.write_redis_args(out);
+        }
+        if let Some(cursor) = &self.cursor {
+            cursor.write_redis_args(out);
+        }
+        match &self.params {
+            Some(params) if !params.is_empty() => {
+                "PARAMS".write_redis_args(out);
+                (params.len() * 2).write_redis_args(out);
+                params.write_redis_args(out);
+            }
+            _ => {}
+        }
+        if let Some(dialect) = self.dialect {
+            "DIALECT".write_redis_args(out);
+            dialect.write_redis_args(out);
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WithCursor {
+    pub count: Option<usize>,
+    pub max_idle: Option<usize>,
+}
+
+impl ToRedisArgs for WithCursor {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        "WITHCURSOR".write_redis_args(out);
+        if let Some(count) = self.count {
+            "COUNT".write_redis_args(out);
+            count.write_redis_args(out);
+        }
+        if let Some(max_idle) = self.max_idle {
+            "MAXIDLE".write_redis_args(out);
+            max_idle.write_redis_args(out);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AggregateStep {
+    Load(Vec<String>),
+    LoadAll,
+    GroupBy {
+        properties: Vec<String>,
+        reducers: Vec<Reducer>,
+    },
+    Apply {
+        expression: String,
+        alias: String,
+    },
+    Filter(String),
+    SortBy {
+        properties: Vec<(String, SortOrder)>,
+        max: Option<usize>,
+    },
+    Limit(Limit),
+}
+
+impl ToRedisArgs for AggregateStep {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        match self {
+            AggregateStep::Load(properties) => {
+                "LOAD".write_redis_args(out);
+                properties.len().write_redis_args(out);
+                properties.write_redis_args(out);
+            }
+            AggregateStep::LoadAll => {
+                "LOAD".write_redis_args(out);
+                "*".write_redis_args(out);
+            }
+            AggregateStep::GroupBy {
+                properties,
+                reducers,
+            } => {
+                "GROUPBY".write_redis_args(out);
+                properties.len().write_redis_args(out);
+                properties.write_redis_args(out);
+                for reducer in reducers {
+                    reducer.write_redis_args(out);
+                }
+            }
+            AggregateStep::Apply { expression, alias } => {
+                "APPLY".write_redis_args(out);
+                expression.write_redis_args(out);
+                "AS".write_redis_args(out);
+                alias.write_redis_args(out);
+            }
+            AggregateStep::Filter(expression) => {
+                "FILTER".write_redis_args(out);
+                expression.write_redis_args(out);
+            }
+            AggregateStep::SortBy { properties, max } => {
+                "SORTBY".write_redis_args(out);
+                (properties.len() * 2).write_redis_args(out);
+                for (property, order) in properties {
+                    property.write_redis_args(out);
+                    match order {
+                        SortOrder::ASC => "ASC".write_redis_args(out),
+                        SortOrder::DESC => "DESC".write_redis_args(out),
+                    }
+                }
+                if let Some(max) = max {
+                    "MAX".write_redis_args(out);
+                    max.write_redis_args(out);
+                }
+            }
+            AggregateStep::Limit(limit) => {
+                "LIMIT".write_redis_args(out);
+                limit.write_redis_args(out);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FTAggregateBuilder {
+    index: String,
+    expr: Expr,
+    steps: Vec<AggregateStep>,
+    cursor: Option<WithCursor>,
+}
+
+impl FTAggregateBuilder {
+    pub fn new(index: &str, expr: Expr) -> FTAggregateBuilder {
+        FTAggregateBuilder {
+            index: index.to_string(),
+            expr,
+            steps: vec![],
+            cursor: None,
+        }
+    }
+
+    /// Builds the FT.AGGREGATE. The SORTBY and LIMIT of the expression are
+    /// appended to the pipeline, search options FT.AGGREGATE has no
+    /// equivalent for are an error.
+    pub fn build(&self) -> crate::Result<FTAggregate> {
+        let search = self.expr.ft_search(&self.index)?;
+        let option = search.option;
+
+        let unsupported = [
+            ("NOCONTENT", option.no_content),
+            ("NOSTOPWORDS", option.no_stopwords),
+            ("WITHSCORES", option.with_scores),
+            ("WITHPAYLOADS", option.with_payloads),
+            ("WITHSORTKEYS", option.with_sort_keys),
+            ("INKEYS", option.in_keys.is_some()),
+            ("INFIELDS", option.in_fields.is_some()),
+            ("RETURN", option.returns.is_some()),
+            ("SLOP", option.slop.is_some()),
+            ("INORDER", option.in_order),
+            ("LANGUAGE", option.language.is_some()),
+            ("EXPANDER", option.expander.is_some()),
+            ("SCORER", option.scorer.is_some()),
+            ("EXPLAINSCORE", option.explain_score),
+            (
+                "SORTBY WITHCOUNT",
+                option.sortby.as_ref().is_some_and(|x| x.with_count),
+            ),
+        ];
+        if let Some((name, _)) = unsupported.iter().find(|(_, set)| *set) {
+            return Err(crate::Error::InvalidQuery(format!(
+                "{} is not supported by FT.AGGREGATE",
+                name
+            )));
+        }
+
+        for step in &self.steps {
+            if let AggregateStep::GroupBy { reducers, .. } = step {
+                if let Some(err) = reducers.iter().find_map(|x| x.error.clone()) {
+                    return Err(err);
+                }
+            }
+            if let AggregateStep::Apply { expression, .. } | AggregateStep::Filter(expression) =
+                step
+            {
+                ApplyExpr::<apply::Bool>::raw(expression)
+                    .properties()
+                    .map_err(|err| {
+                        crate::Error::InvalidQuery(format!(
+                            "invalid expression `{}`: {}",
+                            expression, err
+                        ))
+                    })?;
+            }
+        }
+
+        let mut steps = self.steps.clone();
+        if let Some(sortby) = option.sortby {
+            steps.push(AggregateStep::SortBy {
+                properties: vec![(
+                    property(&sortby.attribute),
+                    if sortby.asc {
+                        SortOrder::ASC
+                    } else {
+                        SortOrder::DESC
+                    },
+                )],
+                max: None,
+            });
+        }
+        if let Some(limit) = option.limit {
+            steps.push(AggregateStep::Limit(limit));
+        }
+
+        Ok(FTAggregate {
+            index: search.index,
+            query: search.query,
+            option: FTAggregateOption {
+                verbatim: option.verbatim,
+                timeout: option.timeout,
+                steps,
+                cursor: self.cursor,
+                params: option.params,
+                dialect: option.dialect,
+            },
+        })
+    }
+
+    /// Reads results through a cursor, `count` rows at a time. An idle cursor
+    /// is deleted by the server after `max_idle` milliseconds.
+    pub fn with_cursor(mut self, count: Option<usize>, max_idle: Option<usize>) -> Self {
+        self.cursor = Some(WithCursor { count, max_idle });
+        self
+    }
+
+    pub fn load(mut self, properties: &[&dyn AsProperty]) -> Self {
+        self.steps.push(AggregateStep::Load(
+            properties.iter().map(|x| property(*x)).collect(),
+        ));
+        self
+    }
+
+    pub fn load_all(mut self) -> Self {
+        self.steps.push(AggregateStep::LoadAll);
+        self
+    }
+
+    pub fn group_by(mut self, properties: &[&dyn AsProperty], reducers: Vec<Reducer>) -> Self {
+        self.steps.push(AggregateStep::GroupBy {
+            properties: properties.iter().map(|x| property(*x)).collect(),
+            reducers,
+        });
+        self
+    }
+
+    pub fn apply<T>(mut self, expression: ApplyExpr<T>, alias: &str) -> Self {
+        self.steps.push(AggregateStep::Apply {
+            expression: expression.as_str().to_string(),
+            alias: alias.to_string(),
+        });
+        self
+    }
+
+    pub fn filter(mut self, expression: ApplyExpr<apply::Bool>) -> Self {
+        self.steps
+            .push(AggregateStep::Filter(expression.as_str().to_string()));
+        self
+    }
+
+    pub fn sort_by(
+        mut self,
+        properties: &[(&dyn AsProperty, SortOrder)],
+        max: Option<usize>,
+    ) -> Self {
+        self.steps.push(AggregateStep::SortBy {
+            properties: properties
+                .iter()
+                .map(|(x, order)| (property(*x), *order))
+                .collect(),
+            max,
+        });
+        self
+    }
+
+    pub fn limit(mut self, offset: usize, num: usize) -> Self {
+        self.steps.push(AggregateStep::Limit(Limit { offset, num }));
+        self
+    }
+}
@@ -0,0 +1,123 @@
+use redis::ToRedisArgs;
+
+use super::property;
+use crate::{Field, NumberField, SortOrder};
+
+#[derive(Debug, Clone)]
+pub struct Reducer {
+    pub function: String,
+    pub args: Vec<String>,
+    pub alias: Option<String>,
+    /// An invalid argument, reported when the aggregation is built.
+    pub(crate) error: Option<crate::Error>,
+}
+
+impl Reducer {
+    pub fn new<T: AsRef<str>>(function: &str, args: &[T]) -> Reducer {
+        Reducer {
+            function: function.to_string(),
+            args: args.iter().map(|x| x.as_ref().to_string()).collect(),
+            alias: None,
+            error: None,
+        }
+    }
+
+    pub fn count() -> Reducer {
+        Reducer::new::<&str>("COUNT", &[])
+    }
+
+    pub fn count_distinct<F: Field + ?Sized>(field: &F) -> Reducer {
+        Reducer::new("COUNT_DISTINCT", &[property(field)])
+    }
+
+    pub fn count_distinctish<F: Field + ?Sized>(field: &F) -> Reducer {
+        Reducer::new("COUNT_DISTINCTISH", &[property(field)])
+    }
+
+    pub fn sum<F: NumberField>(field: &F) -> Reducer {
+        Reducer::new("SUM", &[property(field)])
+    }
+
+    pub fn min<F: NumberField>(field: &F) -> Reducer {
+        Reducer::new("MIN", &[property(field)])
+    }
+
+    pub fn max<F: NumberField>(field: &F) -> Reducer {
+        Reducer::new("MAX", &[property(field)])
+    }
+
+    pub fn avg<F: NumberField>(field: &F) -> Reducer {
+        Reducer::new("AVG", &[property(field)])
+    }
+
+    pub fn stddev<F: NumberField>(field: &F) -> Reducer {
+        Reducer::new("STDDEV", &[property(field)])
+    }
+
+    /// `quantile` is a value between 0 and 1, e.g. 0.5 for the median.
+    pub fn quantile<F: NumberField>(field: &F, quantile: f64) -> Reducer {
+        let mut reducer = Reducer::new("QUANTILE", &[property(field), quantile.to_string()]);
+        if !(0.0..=1.0).contains(&quantile) {
+            reducer.error = Some(crate::Error::InvalidQuery(format!(
+                "quantile must be between 0 and 1, got {}",
+                quantile
+            )));
+        }
+        reducer
+    }
+
+    pub fn to_list<F: Field + ?Sized>(field: &F) -> Reducer {
+        Reducer::new("TOLIST", &[property(field)])
+    }
+
+    pub fn first_value<F: Field + ?Sized>(field: &F) -> Reducer {
+        Reducer::new("FIRST_VALUE", &[property(field)])
+    }
+
+    pub fn first_value_by<F: Field + ?Sized, B: Field + ?Sized>(
+        field: &F,
+        by: &B,
+        order: SortOrder,
+    ) -> Reducer {
+        let order = match order {
+            SortOrder::ASC => "ASC",
+            SortOrder::DESC => "DESC",
+        };
+        Reducer::new(
+            "FIRST_VALUE",
+            &[
+                property(field),
+                "BY".to_string(),
+                property(by),
+                order.to_string(),
+            ],
+        )
+    }
+
+    pub fn random_sample<F: Field + ?Sized>(field: &F, size: usize) -> Reducer {
+        Reducer::new("RANDOM_SAMPLE", &[property(field), size.to_string()])
+    }
+
+    pub fn alias(self, alias: &str) -> Reducer {
+        Reducer {
+            alias: Some(alias.to_string()),
+            ..self
+        }
+    }
+}
+
+impl ToRedisArgs for Reducer {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        "REDUCE".write_redis_args(out);
+        self.function.write_redis_args(out);
+        self.args.len().write_redis_args(out);
+        self.args.write_redis_args(out);
+        if let Some(alias) = &self.alias {
+            "AS".write_redis_args(out);
+            alias.write_redis_args(out);
+        }
+    }
+}
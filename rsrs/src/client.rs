@@ -0,0 +1,110 @@
+use std::future::Future;
+
+use redis::{aio, ConnectionLike, FromRedisValue, Value};
+
+use crate::{
+    aggregate::{AggregateResults, FTAggregate},
+    query::FTSearch,
+    DecodeDocument, FTCreate, IndexInfo, SearchResults,
+};
+
+pub(crate) fn drop_index_cmd(index: &str, delete_documents: bool) -> redis::Cmd {
+    let mut cmd = redis::cmd("FT.DROPINDEX");
+    cmd.arg(index);
+    if delete_documents {
+        cmd.arg("DD");
+    }
+    cmd
+}
+
+pub(crate) fn info_cmd(index: &str) -> redis::Cmd {
+    let mut cmd = redis::cmd("FT.INFO");
+    cmd.arg(index);
+    cmd
+}
+
+/// Runs rsrs commands on any async redis-rs connection.
+///
+/// Server errors such as an unknown index are mapped into [`crate::Error`].
+pub trait AsyncSearchCommands: aio::ConnectionLike + Send + Sized {
+    fn ft_create(&mut self, create: &FTCreate) -> impl Future<Output = crate::Result<()>> + Send {
+        let valid = create.validate();
+        let cmd = create.to_cmd();
+        async move {
+            valid?;
+            let _: Value = cmd.query_async(self).await?;
+            Ok(())
+        }
+    }
+
+    fn ft_search<T: DecodeDocument>(
+        &mut self,
+        search: &FTSearch,
+    ) -> impl Future<Output = crate::Result<SearchResults<T>>> + Send {
+        let cmd = search.to_cmd();
+        let option = search.option.clone();
+        async move { SearchResults::from_reply(cmd.query_async(self).await?, &option) }
+    }
+
+    fn ft_aggregate<T: FromRedisValue>(
+        &mut self,
+        aggregate: &FTAggregate,
+    ) -> impl Future<Output = crate::Result<AggregateResults<T>>> + Send {
+        let cmd = aggregate.to_cmd();
+        async move { AggregateResults::from_reply(cmd.query_async(self).await?) }
+    }
+
+    fn ft_drop(
+        &mut self,
+        index: &str,
+        delete_documents: bool,
+    ) -> impl Future<Output = crate::Result<()>> + Send {
+        let cmd = drop_index_cmd(index, delete_documents);
+        async move {
+            let _: Value = cmd.query_async(self).await?;
+            Ok(())
+        }
+    }
+
+    fn ft_info(&mut self, index: &str) -> impl Future<Output = crate::Result<IndexInfo>> + Send {
+        let cmd = info_cmd(index);
+        async move { IndexInfo::from_reply(cmd.query_async(self).await?) }
+    }
+}
+
+impl<C: aio::ConnectionLike + Send> AsyncSearchCommands for C {}
+
+/// The blocking counterpart of [`AsyncSearchCommands`], its methods suffixed
+/// with `_blocking` like those of [`crate::Index`].
+pub trait SearchCommands: ConnectionLike + Sized {
+    fn ft_create_blocking(&mut self, create: &FTCreate) -> crate::Result<()> {
+        create.validate()?;
+        let _: Value = create.to_cmd().query(self)?;
+        Ok(())
+    }
+
+    fn ft_search_blocking<T: DecodeDocument>(
+        &mut self,
+        search: &FTSearch,
+    ) -> crate::Result<SearchResults<T>> {
+        SearchResults::from_reply(search.to_cmd().query(self)?, &search.option)
+    }
+
+    fn ft_aggregate_blocking<T: FromRedisValue>(
+        &mut self,
+        aggregate: &FTAggregate,
+    ) -> crate::Result<AggregateResults<T>> {
+        AggregateResults::from_reply(aggregate.to_cmd().query(self)?)
+    }
+
+    fn ft_drop_blocking(&mut self, index: &str, delete_documents: bool) -> crate::Result<()> {
+        let _: Value = drop_index_cmd(index, delete_documents).query(self)?;
+        Ok(())
+    }
+
+    fn ft_info_blocking(&mut self, index: &str) -> crate::Result<IndexInfo> {
+        IndexInfo::from_reply(info_cmd(index).query(self)?)
+    }
+}
+
+impl<C: ConnectionLike> SearchCommands for C {}
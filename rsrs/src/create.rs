@@ -1,5 +1,7 @@
 use redis::ToRedisArgs;
 
+use crate::aggregate::{apply::Bool, ApplyExpr};
+
 #[derive(Debug)]
 pub struct FTCreate {
     pub name: String,
@@ -21,14 +23,41 @@ impl FTCreate {
             schema,
         }
     }
+
+    /// Checks the FILTER expression, which may only reference properties of
+    /// the schema or the special `@__key` and `@__score` ones.
+    pub fn validate(&self) -> crate::Result<()> {
+        let Some(filter) = &self.option.filter else {
+            return Ok(());
+        };
+        for property in filter.properties().map_err(crate::Error::InvalidFilter)? {
+            let known = matches!(property.as_str(), "__key" | "__score")
+                || self
+                    .schema
+                    .iter()
+                    .any(|x| x.attribute.as_deref().unwrap_or(x.identifier.as_str()) == property);
+            if !known {
+                return Err(crate::Error::InvalidFilter(format!(
+                    "unknown property '@{}'",
+                    property
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_cmd(&self) -> redis::Cmd {
+        let mut cmd = redis::cmd("FT.CREATE");
+        cmd.arg(self);
+        cmd
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SchemaField {
     pub identifier: String,
     pub attribute: Option<String>,
-    pub field_type: &'static str,
-    pub options: FieldOption,
+    pub kind: FieldKind,
 }
 
 impl SchemaField {
@@ -37,7 +66,7 @@ impl SchemaField {
             return;
         }
 
-        let attribute = std::mem::replace(&mut self.identifier, String::new());
+        let attribute = std::mem::take(&mut self.identifier);
         self.identifier = format!("$.{}", attribute);
         self.attribute = Some(attribute);
     }
@@ -71,8 +100,7 @@ impl redis::ToRedisArgs for SchemaField {
             "AS".write_redis_args(out);
             attribute.write_redis_args(out);
         }
-        self.field_type.write_redis_args(out);
-        self.options.write_redis_args(out);
+        self.kind.write_redis_args(out);
     }
 }
 
@@ -80,9 +108,58 @@ impl redis::ToRedisArgs for SchemaField {
 pub struct IndexOption {
     pub on: Option<DataType>,
     pub prefix: Option<Vec<String>>,
-    pub filter: Option<String>,
-    pub language: Option<&'static str>,
+    pub filter: Option<ApplyExpr<Bool>>,
+    pub language: Option<Language>,
+    pub language_field: Option<String>,
     pub score: Option<f32>,
+    pub score_field: Option<String>,
+    pub payload_field: Option<String>,
+    pub max_text_fields: Option<bool>,
+    /// Seconds of inactivity after which the index expires.
+    pub temporary: Option<usize>,
+    pub no_offsets: Option<bool>,
+    pub no_hl: Option<bool>,
+    pub no_fields: Option<bool>,
+    pub no_freqs: Option<bool>,
+    /// `Some(vec![])` disables stopwords altogether.
+    pub stopwords: Option<Vec<String>>,
+    pub skip_initial_scan: Option<bool>,
+}
+
+impl IndexOption {
+    /// Fills every option unset in `self` from `other`. The flags are unset
+    /// when `None`, so `Some(false)` turns off a flag set in `other`.
+    pub fn or(self, other: IndexOption) -> Self {
+        IndexOption {
+            on: self.on.or(other.on),
+            prefix: self.prefix.or(other.prefix),
+            filter: self.filter.or(other.filter),
+            language: self.language.or(other.language),
+            language_field: self.language_field.or(other.language_field),
+            score: self.score.or(other.score),
+            score_field: self.score_field.or(other.score_field),
+            payload_field: self.payload_field.or(other.payload_field),
+            max_text_fields: self.max_text_fields.or(other.max_text_fields),
+            temporary: self.temporary.or(other.temporary),
+            no_offsets: self.no_offsets.or(other.no_offsets),
+            no_hl: self.no_hl.or(other.no_hl),
+            no_fields: self.no_fields.or(other.no_fields),
+            no_freqs: self.no_freqs.or(other.no_freqs),
+            stopwords: self.stopwords.or(other.stopwords),
+            skip_initial_scan: self.skip_initial_scan.or(other.skip_initial_scan),
+        }
+    }
+
+    /// Adds `prefix` to the PREFIX list unless it is already covered.
+    pub fn with_key_prefix(mut self, prefix: Option<&str>) -> Self {
+        if let Some(prefix) = prefix {
+            let prefixes = self.prefix.get_or_insert_with(Vec::new);
+            if !prefixes.iter().any(|p| prefix.starts_with(p.as_str())) {
+                prefixes.push(prefix.to_string());
+            }
+        }
+        self
+    }
 }
 
 impl ToRedisArgs for IndexOption {
@@ -103,16 +180,57 @@ impl ToRedisArgs for IndexOption {
         }
         if let Some(filter) = &self.filter {
             out.write_arg("FILTER".as_bytes());
-            out.write_arg(filter.as_bytes());
+            out.write_arg(filter.as_str().as_bytes());
         }
         if let Some(language) = &self.language {
             out.write_arg("LANGUAGE".as_bytes());
-            out.write_arg(language.as_bytes());
+            language.write_redis_args(out);
+        }
+        if let Some(language_field) = &self.language_field {
+            out.write_arg("LANGUAGE_FIELD".as_bytes());
+            out.write_arg(language_field.as_bytes());
         }
         if let Some(score) = &self.score {
             out.write_arg("SCORE".as_bytes());
             out.write_arg_fmt(score);
         }
+        if let Some(score_field) = &self.score_field {
+            out.write_arg("SCORE_FIELD".as_bytes());
+            out.write_arg(score_field.as_bytes());
+        }
+        if let Some(payload_field) = &self.payload_field {
+            out.write_arg("PAYLOAD_FIELD".as_bytes());
+            out.write_arg(payload_field.as_bytes());
+        }
+        if self.max_text_fields == Some(true) {
+            out.write_arg("MAXTEXTFIELDS".as_bytes());
+        }
+        if let Some(temporary) = &self.temporary {
+            out.write_arg("TEMPORARY".as_bytes());
+            out.write_arg_fmt(temporary);
+        }
+        if self.no_offsets == Some(true) {
+            out.write_arg("NOOFFSETS".as_bytes());
+        }
+        if self.no_hl == Some(true) {
+            out.write_arg("NOHL".as_bytes());
+        }
+        if self.no_fields == Some(true) {
+            out.write_arg("NOFIELDS".as_bytes());
+        }
+        if self.no_freqs == Some(true) {
+            out.write_arg("NOFREQS".as_bytes());
+        }
+        if let Some(stopwords) = &self.stopwords {
+            out.write_arg("STOPWORDS".as_bytes());
+            out.write_arg_fmt(stopwords.len());
+            for stopword in stopwords {
+                out.write_arg(stopword.as_bytes());
+            }
+        }
+        if self.skip_initial_scan == Some(true) {
+            out.write_arg("SKIPINITIALSCAN".as_bytes());
+        }
     }
 }
 
@@ -131,111 +249,423 @@ impl DataType {
     }
 }
 
+/// A stemming language supported by RediSearch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Arabic,
+    Armenian,
+    Basque,
+    Catalan,
+    Chinese,
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hindi,
+    Hungarian,
+    Indonesian,
+    Irish,
+    Italian,
+    Lithuanian,
+    Nepali,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Serbian,
+    Spanish,
+    Swedish,
+    Tamil,
+    Turkish,
+    Yiddish,
+}
+
+impl Language {
+    pub const ALL: &'static [Language] = &[
+        Language::Arabic,
+        Language::Armenian,
+        Language::Basque,
+        Language::Catalan,
+        Language::Chinese,
+        Language::Danish,
+        Language::Dutch,
+        Language::English,
+        Language::Finnish,
+        Language::French,
+        Language::German,
+        Language::Greek,
+        Language::Hindi,
+        Language::Hungarian,
+        Language::Indonesian,
+        Language::Irish,
+        Language::Italian,
+        Language::Lithuanian,
+        Language::Nepali,
+        Language::Norwegian,
+        Language::Portuguese,
+        Language::Romanian,
+        Language::Russian,
+        Language::Serbian,
+        Language::Spanish,
+        Language::Swedish,
+        Language::Tamil,
+        Language::Turkish,
+        Language::Yiddish,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Arabic => "arabic",
+            Language::Armenian => "armenian",
+            Language::Basque => "basque",
+            Language::Catalan => "catalan",
+            Language::Chinese => "chinese",
+            Language::Danish => "danish",
+            Language::Dutch => "dutch",
+            Language::English => "english",
+            Language::Finnish => "finnish",
+            Language::French => "french",
+            Language::German => "german",
+            Language::Greek => "greek",
+            Language::Hindi => "hindi",
+            Language::Hungarian => "hungarian",
+            Language::Indonesian => "indonesian",
+            Language::Irish => "irish",
+            Language::Italian => "italian",
+            Language::Lithuanian => "lithuanian",
+            Language::Nepali => "nepali",
+            Language::Norwegian => "norwegian",
+            Language::Portuguese => "portuguese",
+            Language::Romanian => "romanian",
+            Language::Russian => "russian",
+            Language::Serbian => "serbian",
+            Language::Spanish => "spanish",
+            Language::Swedish => "swedish",
+            Language::Tamil => "tamil",
+            Language::Turkish => "turkish",
+            Language::Yiddish => "yiddish",
+        }
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Language::ALL
+            .iter()
+            .find(|x| x.as_str().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| format!("unknown language '{}'", s))
+    }
+}
+
+impl ToRedisArgs for Language {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        self.as_str().write_redis_args(out)
+    }
+}
+
 pub struct Prefix {
     pub prefixs: Vec<&'static str>,
 }
 
-#[derive(Default, Debug)]
-pub struct FieldOption {
-    pub sortable: Option<bool>,
-    pub unf: Option<bool>,
-    pub nostem: Option<bool>,
-    pub noindex: Option<bool>,
-    pub phonetic: Option<crate::field::PhoneticMatcher>,
-    pub weight: Option<f32>,
-    pub separator: Option<&'static str>,
-    pub casesensitive: Option<bool>,
-    pub withsuffixtrie: Option<bool>,
-    // vector options
-    pub algorithm: Option<crate::field::VectorAlgorithm>,
-    pub count: Option<usize>,
-    pub vector_type: Option<crate::field::VectorType>,
-    pub dim: Option<usize>,
-    pub distance_metric: Option<crate::field::DistanceMetric>,
-    pub initial_cap: Option<usize>,
-    pub block_size: Option<usize>,
-    pub m: Option<usize>,
-    pub ef_construction: Option<usize>,
-    pub ef_runtime: Option<usize>,
-    pub epsilon: Option<usize>,
+/// The type of a schema field and the options valid for it.
+#[derive(Debug, Clone)]
+pub enum FieldKind {
+    Text(TextOptions),
+    Tag(TagOptions),
+    Numeric(NumericOptions),
+    Geo(GeoOptions),
+    GeoShape(GeoShapeOptions),
+    Vector(VectorOptions),
+}
+
+impl FieldKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldKind::Text(_) => "TEXT",
+            FieldKind::Tag(_) => "TAG",
+            FieldKind::Numeric(_) => "NUMERIC",
+            FieldKind::Geo(_) => "GEO",
+            FieldKind::GeoShape(_) => "GEOSHAPE",
+            FieldKind::Vector(_) => "VECTOR",
+        }
+    }
 }
 
-impl ToRedisArgs for FieldOption {
+impl ToRedisArgs for FieldKind {
     fn write_redis_args<W>(&self, out: &mut W)
     where
         W: ?Sized + redis::RedisWrite,
     {
-        if self.sortable.unwrap_or_default() {
-            out.write_arg("SORTABLE".as_bytes());
+        out.write_arg(self.as_str().as_bytes());
+        match self {
+            FieldKind::Text(options) => options.write_redis_args(out),
+            FieldKind::Tag(options) => options.write_redis_args(out),
+            FieldKind::Numeric(options) => options.write_redis_args(out),
+            FieldKind::Geo(options) => options.write_redis_args(out),
+            FieldKind::GeoShape(options) => options.write_redis_args(out),
+            FieldKind::Vector(options) => options.write_redis_args(out),
         }
-        if self.unf.unwrap_or_default() {
+    }
+}
+
+/// SORTABLE, with UNF only being valid on a sortable field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sortable {
+    Sortable,
+    SortableUnf,
+}
+
+impl Sortable {
+    pub fn new(sortable: bool, unf: bool) -> Option<Sortable> {
+        match (sortable, unf) {
+            (false, _) => None,
+            (true, false) => Some(Sortable::Sortable),
+            (true, true) => Some(Sortable::SortableUnf),
+        }
+    }
+}
+
+impl ToRedisArgs for Sortable {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        out.write_arg("SORTABLE".as_bytes());
+        if *self == Sortable::SortableUnf {
             out.write_arg("UNF".as_bytes());
         }
-        if self.nostem.unwrap_or_default() {
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TextOptions {
+    pub no_stem: bool,
+    pub weight: Option<f32>,
+    pub phonetic: Option<crate::field::PhoneticMatcher>,
+    pub with_suffix_trie: bool,
+    pub sortable: Option<Sortable>,
+    pub no_index: bool,
+}
+
+impl ToRedisArgs for TextOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        if self.no_stem {
             out.write_arg("NOSTEM".as_bytes());
         }
-        if self.noindex.unwrap_or_default() {
-            out.write_arg("NOINDEX".as_bytes());
-        }
-        if let Some(p) = &self.phonetic {
-            out.write_arg("PHONETIC".as_bytes());
-            out.write_arg(p.as_str().as_bytes());
-        }
         if let Some(weight) = self.weight {
             out.write_arg("WEIGHT".as_bytes());
             out.write_arg_fmt(weight);
         }
-        if let Some(sep) = &self.separator {
+        if let Some(phonetic) = &self.phonetic {
+            out.write_arg("PHONETIC".as_bytes());
+            out.write_arg(phonetic.as_str().as_bytes());
+        }
+        if self.with_suffix_trie {
+            out.write_arg("WITHSUFFIXTRIE".as_bytes());
+        }
+        if let Some(sortable) = &self.sortable {
+            sortable.write_redis_args(out);
+        }
+        if self.no_index {
+            out.write_arg("NOINDEX".as_bytes());
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TagOptions {
+    pub separator: Option<char>,
+    pub case_sensitive: bool,
+    pub with_suffix_trie: bool,
+    pub index_empty: bool,
+    pub index_missing: bool,
+    pub sortable: Option<Sortable>,
+    pub no_index: bool,
+}
+
+impl ToRedisArgs for TagOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        if let Some(separator) = self.separator {
             out.write_arg("SEPARATOR".as_bytes());
-            out.write_arg(sep.as_bytes());
+            out.write_arg(separator.to_string().as_bytes());
         }
-        if self.casesensitive.unwrap_or_default() {
+        if self.case_sensitive {
             out.write_arg("CASESENSITIVE".as_bytes());
         }
-        if self.withsuffixtrie.unwrap_or_default() {
+        if self.with_suffix_trie {
             out.write_arg("WITHSUFFIXTRIE".as_bytes());
         }
-        if let Some(algorithm) = &self.algorithm {
-            algorithm.write_redis_args(out);
+        if self.index_empty {
+            out.write_arg("INDEXEMPTY".as_bytes());
         }
-        if let Some(count) = self.count {
-            out.write_arg_fmt(count);
+        if self.index_missing {
+            out.write_arg("INDEXMISSING".as_bytes());
         }
-        if let Some(vector_type) = &self.vector_type {
-            out.write_arg("TYPE".as_bytes());
-            vector_type.write_redis_args(out);
+        if let Some(sortable) = &self.sortable {
+            sortable.write_redis_args(out);
         }
-        if let Some(dim) = self.dim {
-            out.write_arg("DIM".as_bytes());
-            out.write_arg_fmt(dim);
+        if self.no_index {
+            out.write_arg("NOINDEX".as_bytes());
         }
-        if let Some(distance_metric) = &self.distance_metric {
-            out.write_arg("DISTANCE_METRIC".as_bytes());
-            distance_metric.write_redis_args(out);
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct NumericOptions {
+    pub sortable: bool,
+    pub no_index: bool,
+}
+
+impl ToRedisArgs for NumericOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        if self.sortable {
+            out.write_arg("SORTABLE".as_bytes());
         }
-        if let Some(initial_cap) = self.initial_cap {
-            out.write_arg("INITIAL_CAP".as_bytes());
-            out.write_arg_fmt(initial_cap);
+        if self.no_index {
+            out.write_arg("NOINDEX".as_bytes());
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct GeoOptions {
+    pub sortable: bool,
+    pub no_index: bool,
+}
+
+impl ToRedisArgs for GeoOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        if self.sortable {
+            out.write_arg("SORTABLE".as_bytes());
         }
-        if let Some(block_size) = self.block_size {
-            out.write_arg("BLOCK_SIZE".as_bytes());
-            out.write_arg_fmt(block_size);
+        if self.no_index {
+            out.write_arg("NOINDEX".as_bytes());
         }
-        if let Some(m) = self.m {
-            out.write_arg("M".as_bytes());
-            out.write_arg_fmt(m);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordSystem {
+    Flat,
+    Spherical,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct GeoShapeOptions {
+    pub coord_system: Option<CoordSystem>,
+    pub no_index: bool,
+}
+
+impl ToRedisArgs for GeoShapeOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        match self.coord_system {
+            Some(CoordSystem::Flat) => out.write_arg("FLAT".as_bytes()),
+            Some(CoordSystem::Spherical) => out.write_arg("SPHERICAL".as_bytes()),
+            None => {}
         }
-        if let Some(ef_construction) = self.ef_construction {
-            out.write_arg("EF_CONSTRUCTION".as_bytes());
-            out.write_arg_fmt(ef_construction);
+        if self.no_index {
+            out.write_arg("NOINDEX".as_bytes());
         }
-        if let Some(ef_runtime) = self.ef_runtime {
-            out.write_arg("EF_RUNTIME".as_bytes());
-            out.write_arg_fmt(ef_runtime);
+    }
+}
+
+/// The index algorithm of a vector field with the parameters valid for it.
+#[derive(Debug, Clone)]
+pub enum VectorIndex {
+    Flat {
+        block_size: Option<usize>,
+    },
+    Hnsw {
+        m: Option<usize>,
+        ef_construction: Option<usize>,
+        ef_runtime: Option<usize>,
+        epsilon: Option<f32>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct VectorOptions {
+    pub index: VectorIndex,
+    pub vector_type: crate::field::VectorType,
+    pub dim: usize,
+    pub distance_metric: crate::field::DistanceMetric,
+    pub initial_cap: Option<usize>,
+}
+
+impl ToRedisArgs for VectorOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        let mut attributes: Vec<Vec<u8>> = vec![];
+        let mut push = |name: &str, value: String| {
+            attributes.push(name.as_bytes().to_vec());
+            attributes.push(value.into_bytes());
+        };
+        push("TYPE", self.vector_type.as_str().to_string());
+        push("DIM", self.dim.to_string());
+        push("DISTANCE_METRIC", self.distance_metric.as_str().to_string());
+        if let Some(initial_cap) = self.initial_cap {
+            push("INITIAL_CAP", initial_cap.to_string());
+        }
+        match &self.index {
+            VectorIndex::Flat { block_size } => {
+                if let Some(block_size) = block_size {
+                    push("BLOCK_SIZE", block_size.to_string());
+                }
+            }
+            VectorIndex::Hnsw {
+                m,
+                ef_construction,
+                ef_runtime,
+                epsilon,
+            } => {
+                if let Some(m) = m {
+                    push("M", m.to_string());
+                }
+                if let Some(ef_construction) = ef_construction {
+                    push("EF_CONSTRUCTION", ef_construction.to_string());
+                }
+                if let Some(ef_runtime) = ef_runtime {
+                    push("EF_RUNTIME", ef_runtime.to_string());
+                }
+                if let Some(epsilon) = epsilon {
+                    push("EPSILON", epsilon.to_string());
+                }
+            }
+        }
+
+        match self.index {
+            VectorIndex::Flat { .. } => out.write_arg("FLAT".as_bytes()),
+            VectorIndex::Hnsw { .. } => out.write_arg("HNSW".as_bytes()),
         }
-        if let Some(epsilon) = self.epsilon {
-            out.write_arg("EPSILON".as_bytes());
-            out.write_arg_fmt(epsilon);
+        out.write_arg_fmt(attributes.len());
+        for attribute in attributes {
+            out.write_arg(&attribute);
         }
     }
 }
@@ -1,7 +1,8 @@
 use crate::{
+    aggregate::FTAggregateBuilder,
     create::{FTCreate, IndexOption},
-    query::FTSearch,
-    Expr,
+    query::FTSearchBuilder,
+    DocumentWrite, EncodeField, Expr,
 };
 
 pub trait Document {
@@ -9,9 +10,50 @@ pub trait Document {
 
     fn op() -> Self::Operator;
 
-    fn search(index: &str, expr: Expr) -> crate::Result<FTSearch> {
-        expr.ft_search(index)
+    fn search(index: &str, expr: Expr) -> FTSearchBuilder {
+        FTSearchBuilder::new(index, expr)
     }
 
+    fn aggregate(index: &str, expr: Expr) -> FTAggregateBuilder {
+        FTAggregateBuilder::new(index, expr)
+    }
+
+    /// The index name set by `#[document(index)]`, the lowercased struct name
+    /// otherwise.
+    fn index_name() -> &'static str;
+
+    /// The index options set by `#[document(...)]`.
+    fn index_option() -> IndexOption {
+        IndexOption::default()
+    }
+
+    /// Creates the index with `option`, falling back to [`Document::index_option`]
+    /// for every option left unset.
     fn create_index(index: &str, option: IndexOption) -> FTCreate;
+
+    /// The indexed fields of this document, by field name.
+    fn fields(&self) -> Vec<(&'static str, &dyn EncodeField)>;
+
+    /// The prefix of every key of this document, set by `#[document(prefix)]`.
+    fn key_prefix() -> Option<&'static str> {
+        None
+    }
+
+    /// The key of this document, its `#[id]` field appended to the key prefix.
+    fn key(&self) -> Option<String> {
+        None
+    }
+
+    /// Builds the HSET or JSON.SET writing this document to its key, picked by
+    /// the data type of the index.
+    fn save(&self, option: &IndexOption) -> crate::Result<DocumentWrite> {
+        match self.key() {
+            Some(key) => Ok(self.save_as(&key, option)),
+            None => Err(crate::Error::MissingId),
+        }
+    }
+
+    fn save_as(&self, key: &str, option: &IndexOption) -> DocumentWrite {
+        DocumentWrite::new(key, option, &self.fields())
+    }
 }
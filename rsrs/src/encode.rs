@@ -0,0 +1,241 @@
+use redis::ToRedisArgs;
+
+use crate::{DataType, IndexOption};
+
+/// Encodes a single field value of a document for HSET or JSON.SET.
+pub trait EncodeField {
+    /// `None` removes the field from the hash.
+    fn encode_hash(&self) -> Option<Vec<u8>>;
+
+    fn encode_json(&self, out: &mut String);
+}
+
+macro_rules! impl_encode_field_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl EncodeField for $ty {
+                fn encode_hash(&self) -> Option<Vec<u8>> {
+                    Some(self.to_string().into_bytes())
+                }
+
+                fn encode_json(&self, out: &mut String) {
+                    out.push_str(&self.to_string());
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_field_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_encode_field_float {
+    ($($ty:ty),*) => {
+        $(
+            impl EncodeField for $ty {
+                fn encode_hash(&self) -> Option<Vec<u8>> {
+                    Some(self.to_string().into_bytes())
+                }
+
+                fn encode_json(&self, out: &mut String) {
+                    if self.is_finite() {
+                        out.push_str(&self.to_string());
+                    } else {
+                        out.push_str("null");
+                    }
+                }
+            }
+
+            impl EncodeField for Vec<$ty> {
+                fn encode_hash(&self) -> Option<Vec<u8>> {
+                    Some(self.iter().flat_map(|x| x.to_le_bytes()).collect())
+                }
+
+                fn encode_json(&self, out: &mut String) {
+                    out.push('[');
+                    for (i, x) in self.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        x.encode_json(out);
+                    }
+                    out.push(']');
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_field_float!(f32, f64);
+
+impl EncodeField for String {
+    fn encode_hash(&self) -> Option<Vec<u8>> {
+        Some(self.as_bytes().to_vec())
+    }
+
+    fn encode_json(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl<T: EncodeField> EncodeField for Option<T> {
+    fn encode_hash(&self) -> Option<Vec<u8>> {
+        self.as_ref().and_then(|x| x.encode_hash())
+    }
+
+    fn encode_json(&self, out: &mut String) {
+        match self {
+            Some(x) => x.encode_json(out),
+            None => out.push_str("null"),
+        }
+    }
+}
+
+/// The write of a whole document.
+///
+/// A hash is written with HSET, and the fields encoded as `None` are removed
+/// with HDEL. A JSON document replaces the value at `$` with JSON.SET, `None`
+/// fields stored as `null`. Only the indexed fields are written: the `#[id]`
+/// is carried by the key, and decoding reads it back from there.
+#[derive(Debug, Clone)]
+pub enum DocumentWrite {
+    HSet {
+        key: String,
+        fields: Vec<(&'static str, Vec<u8>)>,
+        removed: Vec<&'static str>,
+    },
+    JsonSet {
+        key: String,
+        json: String,
+    },
+    JsonMerge {
+        key: String,
+        json: String,
+    },
+}
+
+impl DocumentWrite {
+    /// Encodes `fields` for the data type the index is created `ON`.
+    pub fn new(
+        key: &str,
+        option: &IndexOption,
+        fields: &[(&'static str, &dyn EncodeField)],
+    ) -> Self {
+        match option.on {
+            Some(DataType::Json) => DocumentWrite::JsonSet {
+                key: key.to_string(),
+                json: encode_json_object(fields),
+            },
+            _ => {
+                let mut set = Vec::new();
+                let mut removed = Vec::new();
+                for (name, value) in fields {
+                    match value.encode_hash() {
+                        Some(value) => set.push((*name, value)),
+                        None => removed.push(*name),
+                    }
+                }
+                DocumentWrite::HSet {
+                    key: key.to_string(),
+                    fields: set,
+                    removed,
+                }
+            }
+        }
+    }
+
+    /// Like [`DocumentWrite::new`], but a JSON document is written with
+    /// JSON.MERGE, keeping the members it does not index. MERGE needs
+    /// RedisJSON 2.6 and removes the `null` members.
+    pub fn merge(
+        key: &str,
+        option: &IndexOption,
+        fields: &[(&'static str, &dyn EncodeField)],
+    ) -> Self {
+        match Self::new(key, option, fields) {
+            DocumentWrite::JsonSet { key, json } => DocumentWrite::JsonMerge { key, json },
+            write => write,
+        }
+    }
+
+    /// The command writing the document, HSET, JSON.SET or JSON.MERGE.
+    pub fn command(&self) -> &'static str {
+        match self {
+            DocumentWrite::HSet { .. } => "HSET",
+            DocumentWrite::JsonSet { .. } => "JSON.SET",
+            DocumentWrite::JsonMerge { .. } => "JSON.MERGE",
+        }
+    }
+
+    /// The write as an atomic pipeline, with the HDEL of the removed hash
+    /// fields if any.
+    pub fn to_pipeline(&self) -> redis::Pipeline {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        match self {
+            DocumentWrite::HSet {
+                key,
+                fields,
+                removed,
+            } => {
+                if !fields.is_empty() {
+                    pipe.cmd(self.command()).arg(self).ignore();
+                }
+                if !removed.is_empty() {
+                    pipe.cmd("HDEL").arg(key).arg(removed).ignore();
+                }
+            }
+            DocumentWrite::JsonSet { .. } | DocumentWrite::JsonMerge { .. } => {
+                pipe.cmd(self.command()).arg(self).ignore();
+            }
+        }
+        pipe
+    }
+}
+
+impl ToRedisArgs for DocumentWrite {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        match self {
+            DocumentWrite::HSet { key, fields, .. } => {
+                key.write_redis_args(out);
+                for (name, value) in fields {
+                    name.write_redis_args(out);
+                    value.write_redis_args(out);
+                }
+            }
+            DocumentWrite::JsonSet { key, json } | DocumentWrite::JsonMerge { key, json } => {
+                key.write_redis_args(out);
+                "$".write_redis_args(out);
+                json.write_redis_args(out);
+            }
+        }
+    }
+}
+
+fn encode_json_object(fields: &[(&'static str, &dyn EncodeField)]) -> String {
+    let mut json = String::from("{");
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        name.to_string().encode_json(&mut json);
+        json.push(':');
+        value.encode_json(&mut json);
+    }
+    json.push('}');
+    json
+}
@@ -1,13 +1,52 @@
+use std::sync::Arc;
+
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Error, Debug, Clone, Copy)]
+#[derive(Error, Debug, Clone)]
 pub enum Error {
     #[error("duplicated vector query")]
     DuplicatedVectorQuery,
-    #[error("duplicated param")]
-    DuplicatedParam,
     #[error("empty query body")]
     EmptyQueryBody,
+    #[error("unexpected reply")]
+    UnexpectedReply,
+    #[error("document has no `#[id]` field")]
+    MissingId,
+    #[error("failed to decode field `{field}`: {reason}")]
+    FieldDecode { field: String, reason: String },
+    #[error("unknown index")]
+    UnknownIndex,
+    #[error("index already exists")]
+    IndexAlreadyExists,
+    #[error("invalid index filter: {0}")]
+    InvalidFilter(String),
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+    #[error("query syntax error: {0}")]
+    QuerySyntax(String),
+    #[error(transparent)]
+    Redis(Arc<redis::RedisError>),
+}
+
+impl From<redis::RedisError> for Error {
+    /// Classifies the error replies of RediSearch by their code and detail.
+    /// `ERR` carries most of them; `Unknown Index name` and
+    /// `<index>: no such index` come back with their first word as the code.
+    fn from(err: redis::RedisError) -> Self {
+        let (Some(code), Some(detail)) = (err.code(), err.detail()) else {
+            return Error::Redis(Arc::new(err));
+        };
+        match (code, detail) {
+            ("ERR", "Unknown Index name" | "Unknown index name" | "no such index")
+            | ("Unknown", "Index name" | "index name") => Error::UnknownIndex,
+            (code, "no such index") if code.ends_with(':') => Error::UnknownIndex,
+            ("ERR", "Index already exists") => Error::IndexAlreadyExists,
+            ("ERR", detail) if detail.starts_with("Syntax error") => {
+                Error::QuerySyntax(detail.to_string())
+            }
+            _ => Error::Redis(Arc::new(err)),
+        }
+    }
 }
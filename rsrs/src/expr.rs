@@ -0,0 +1,850 @@
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use itertools::Itertools;
+
+use crate::query::{impl_search_option, FTSearch, FTSearchOption, FTSearchParam};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    ASC,
+    DESC,
+}
+
+/// A node of the query tree held by [`Expr`].
+///
+/// Nodes are only turned into query text by [`Expr::ft_search`], so a tree can
+/// be inspected or rewritten freely before that.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+    Not(Box<QueryNode>),
+    /// Boosts matching documents without filtering, rendered as `~`.
+    Optional(Box<QueryNode>),
+    /// A sub-query carrying `=>{...}` attributes.
+    Attributed(Box<QueryNode>, QueryAttributes),
+    Numeric(NumericPredicate),
+    Tag(TagPredicate),
+    Text(TextPredicate),
+    Geo(GeoPredicate),
+    /// Matches every document, rendered as `*`.
+    All,
+    /// A KNN query, always the root of the tree.
+    Knn(KnnQuery),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumericPredicate {
+    pub field: &'static str,
+    pub min: NumericBound,
+    pub max: NumericBound,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumericBound {
+    Included(String),
+    Excluded(String),
+    Unbounded,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagPredicate {
+    pub field: &'static str,
+    pub values: Vec<TagValue>,
+}
+
+/// A single alternative of a tag predicate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagValue {
+    Exact(String),
+    /// `foo*`
+    Prefix(String),
+    /// `*foo`
+    Suffix(String),
+    /// `*foo*`
+    Infix(String),
+    /// `w'foo?b*'`, needs DIALECT 2.
+    Wildcard(String),
+    /// `$name`, the value being sent through PARAMS.
+    Param(String),
+}
+
+impl TagValue {
+    fn render(&self, out: &mut String) {
+        match self {
+            TagValue::Exact(v) => out.push_str(&escape(v)),
+            TagValue::Prefix(v) => out.push_str(&format!("{}*", escape(v))),
+            TagValue::Suffix(v) => out.push_str(&format!("*{}", escape(v))),
+            TagValue::Infix(v) => out.push_str(&format!("*{}*", escape(v))),
+            TagValue::Wildcard(v) => out.push_str(&format!("w'{}'", escape_quoted(v, '\''))),
+            TagValue::Param(name) => out.push_str(&format!("${}", name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextPredicate {
+    pub field: &'static str,
+    pub values: Vec<TextValue>,
+    /// Whether the field has a phonetic matcher.
+    pub phonetic: bool,
+}
+
+/// A single alternative of a text predicate, several are rendered as a union.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextValue {
+    Term(String),
+    /// `"foo bar"`
+    Phrase(String),
+    /// `foo*`
+    Prefix(String),
+    /// `*foo`
+    Suffix(String),
+    /// `*foo*`
+    Infix(String),
+    /// `%foo%`, one pair of `%` per Levenshtein distance, from 1 to 3.
+    Fuzzy(String, usize),
+    /// `w'foo?b*'`, needs DIALECT 2.
+    Wildcard(String),
+    /// `$name`, the value being sent through PARAMS.
+    Param(String),
+}
+
+impl TextValue {
+    fn render(&self, out: &mut String) {
+        match self {
+            TextValue::Term(v) => out.push_str(&escape(v)),
+            // Words of a phrase are lexed as usual, so each is escaped.
+            TextValue::Phrase(v) => out.push_str(&format!(
+                "\"{}\"",
+                v.split_whitespace()
+                    .map(escape)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )),
+            TextValue::Prefix(v) => out.push_str(&format!("{}*", escape(v))),
+            TextValue::Suffix(v) => out.push_str(&format!("*{}", escape(v))),
+            TextValue::Infix(v) => out.push_str(&format!("*{}*", escape(v))),
+            TextValue::Fuzzy(v, distance) => {
+                let marks = "%".repeat(*distance);
+                out.push_str(&format!("{}{}{}", marks, escape(v), marks))
+            }
+            TextValue::Wildcard(v) => out.push_str(&format!("w'{}'", escape_quoted(v, '\''))),
+            TextValue::Param(name) => out.push_str(&format!("${}", name)),
+        }
+    }
+}
+
+/// Escapes `value` so that it is read as a single term: every ASCII
+/// punctuation character but `_`, and every whitespace, gets a backslash.
+pub fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if (c.is_ascii_punctuation() && c != '_') || c.is_whitespace() {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// PARAMS names are rendered unescaped, so they must match `[A-Za-z0-9_]+`.
+pub(crate) fn check_param_name(name: &str) -> crate::Result<()> {
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+        return Err(crate::Error::InvalidQuery(format!(
+            "invalid param name `{}`",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Escapes the closing `quote` and backslashes of a quoted value.
+fn escape_quoted(value: &str, quote: char) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == quote || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Per-clause attributes, rendered as `=>{ $weight: 2; $slop: 1; }`.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct QueryAttributes {
+    pub weight: Option<f64>,
+    pub slop: Option<usize>,
+    pub in_order: Option<bool>,
+    pub phonetic: Option<bool>,
+}
+
+impl QueryAttributes {
+    fn render(&self, out: &mut String) {
+        out.push_str("=>{");
+        if let Some(weight) = self.weight {
+            out.push_str(&format!(" $weight: {};", weight));
+        }
+        if let Some(slop) = self.slop {
+            out.push_str(&format!(" $slop: {};", slop));
+        }
+        if let Some(in_order) = self.in_order {
+            out.push_str(&format!(" $inorder: {};", in_order));
+        }
+        if let Some(phonetic) = self.phonetic {
+            out.push_str(&format!(" $phonetic: {};", phonetic));
+        }
+        out.push_str(" }");
+    }
+
+    /// Checks the attributes against the predicates of `node`: `$slop` and
+    /// `$inorder` need a TEXT predicate, `$phonetic` needs every TEXT
+    /// predicate to be on a field with a phonetic matcher.
+    fn validate(&self, node: &QueryNode) -> Result<(), String> {
+        if let Some(weight) = self.weight {
+            if !weight.is_finite() || weight <= 0. {
+                return Err(format!("$weight must be positive, got {}", weight));
+            }
+        }
+        let mut texts = vec![];
+        node.walk(&mut |node| {
+            if let QueryNode::Text(text) = node {
+                texts.push((text.field, text.phonetic));
+            }
+        });
+        if texts.is_empty() {
+            for (name, set) in [
+                ("$slop", self.slop.is_some()),
+                ("$inorder", self.in_order.is_some()),
+                ("$phonetic", self.phonetic.is_some()),
+            ] {
+                if set {
+                    return Err(format!("{} only applies to TEXT fields", name));
+                }
+            }
+        }
+        if self.phonetic == Some(true) {
+            if let Some((field, _)) = texts.iter().find(|(_, phonetic)| !phonetic) {
+                return Err(format!("$phonetic needs a phonetic matcher on `{}`", field));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The unit of a geo radius.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeoUnit {
+    M,
+    Km,
+    Mi,
+    Ft,
+}
+
+impl GeoUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::M => "m",
+            Self::Km => "km",
+            Self::Mi => "mi",
+            Self::Ft => "ft",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoPredicate {
+    pub field: &'static str,
+    pub lon: f32,
+    pub lat: f32,
+    pub radius: usize,
+    pub unit: GeoUnit,
+    /// Names of the PARAMS holding `lon`, `lat` and `radius`, if bound.
+    pub params: Option<[String; 3]>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnnQuery {
+    pub filter: Option<Box<QueryNode>>,
+    pub k: usize,
+    pub field: &'static str,
+    /// Name of the PARAMS holding the query vector, without the `$`.
+    pub param: String,
+}
+
+impl QueryNode {
+    pub fn is_leaf(&self) -> bool {
+        !matches!(
+            self,
+            QueryNode::And(_)
+                | QueryNode::Or(_)
+                | QueryNode::Not(_)
+                | QueryNode::Optional(_)
+                | QueryNode::Attributed(..)
+                | QueryNode::Knn(_)
+        )
+    }
+
+    /// Whether the node renders as a single unit, without extra parentheses.
+    fn is_atom(&self) -> bool {
+        self.is_leaf() || matches!(self, QueryNode::Attributed(..))
+    }
+
+    /// Direct children of this node, in rendering order.
+    pub fn children(&self) -> Vec<&QueryNode> {
+        match self {
+            QueryNode::And(nodes) | QueryNode::Or(nodes) => nodes.iter().collect(),
+            QueryNode::Not(node) | QueryNode::Optional(node) | QueryNode::Attributed(node, _) => {
+                vec![node]
+            }
+            QueryNode::Knn(knn) => knn.filter.iter().map(|x| x.as_ref()).collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut QueryNode> {
+        match self {
+            QueryNode::And(nodes) | QueryNode::Or(nodes) => nodes.iter_mut().collect(),
+            QueryNode::Not(node) | QueryNode::Optional(node) | QueryNode::Attributed(node, _) => {
+                vec![node]
+            }
+            QueryNode::Knn(knn) => knn.filter.iter_mut().map(|x| x.as_mut()).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Visits every node of the tree in pre-order.
+    pub fn walk<F: FnMut(&QueryNode)>(&self, f: &mut F) {
+        f(self);
+        for child in self.children() {
+            child.walk(f);
+        }
+    }
+
+    /// Visits every node of the tree in pre-order, allowing it to be rewritten.
+    pub fn walk_mut<F: FnMut(&mut QueryNode)>(&mut self, f: &mut F) {
+        f(self);
+        for child in self.children_mut() {
+            child.walk_mut(f);
+        }
+    }
+
+    fn and(self, other: QueryNode) -> QueryNode {
+        match (self, other) {
+            (QueryNode::And(mut left), QueryNode::And(right)) => {
+                left.extend(right);
+                QueryNode::And(left)
+            }
+            (QueryNode::And(mut left), right) => {
+                left.push(right);
+                QueryNode::And(left)
+            }
+            (left, QueryNode::And(mut right)) => {
+                right.insert(0, left);
+                QueryNode::And(right)
+            }
+            (left, right) => QueryNode::And(vec![left, right]),
+        }
+    }
+
+    fn or(self, other: QueryNode) -> QueryNode {
+        match (self, other) {
+            (QueryNode::Or(mut left), QueryNode::Or(right)) => {
+                left.extend(right);
+                QueryNode::Or(left)
+            }
+            (QueryNode::Or(mut left), right) => {
+                left.push(right);
+                QueryNode::Or(left)
+            }
+            (left, QueryNode::Or(mut right)) => {
+                right.insert(0, left);
+                QueryNode::Or(right)
+            }
+            (left, right) => QueryNode::Or(vec![left, right]),
+        }
+    }
+
+    /// Renames the PARAMS referenced by this tree, `names` mapping old names
+    /// to new ones.
+    fn rename_params(&mut self, names: &BTreeMap<String, String>) {
+        if names.is_empty() {
+            return;
+        }
+        let rename = |name: &mut String| {
+            if let Some(renamed) = names.get(name.as_str()) {
+                *name = renamed.clone();
+            }
+        };
+        self.walk_mut(&mut |node| match node {
+            QueryNode::Numeric(numeric) => {
+                for bound in [&mut numeric.min, &mut numeric.max] {
+                    if let NumericBound::Included(v) | NumericBound::Excluded(v) = bound {
+                        if let Some(renamed) = v.strip_prefix('$').and_then(|x| names.get(x)) {
+                            *v = format!("${}", renamed);
+                        }
+                    }
+                }
+            }
+            QueryNode::Tag(tag) => {
+                for value in tag.values.iter_mut() {
+                    if let TagValue::Param(name) = value {
+                        rename(name);
+                    }
+                }
+            }
+            QueryNode::Text(text) => {
+                for value in text.values.iter_mut() {
+                    if let TextValue::Param(name) = value {
+                        rename(name);
+                    }
+                }
+            }
+            QueryNode::Geo(geo) => {
+                for name in geo.params.iter_mut().flatten() {
+                    rename(name);
+                }
+            }
+            QueryNode::Knn(knn) => rename(&mut knn.param),
+            _ => {}
+        });
+    }
+
+    pub(crate) fn to_query(&self) -> String {
+        let mut out = String::new();
+        self.render(&mut out);
+        out
+    }
+
+    fn render(&self, out: &mut String) {
+        match self {
+            QueryNode::And(nodes) => {
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                    }
+                    node.render_grouped(out, matches!(node, QueryNode::Or(x) if x.len() > 1));
+                }
+            }
+            QueryNode::Or(nodes) => {
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(" | ");
+                    }
+                    node.render_grouped(out, matches!(node, QueryNode::And(x) if x.len() > 1));
+                }
+            }
+            QueryNode::Not(node) => {
+                out.push('-');
+                node.render_grouped(out, !node.is_atom());
+            }
+            QueryNode::Optional(node) => {
+                out.push('~');
+                node.render_grouped(out, !node.is_atom());
+            }
+            QueryNode::Attributed(node, attributes) => {
+                node.render_grouped(out, true);
+                attributes.render(out);
+            }
+            QueryNode::Numeric(numeric) => {
+                let min = match &numeric.min {
+                    NumericBound::Included(v) => v.clone(),
+                    NumericBound::Excluded(v) => format!("({}", v),
+                    NumericBound::Unbounded => "-inf".to_string(),
+                };
+                let max = match &numeric.max {
+                    NumericBound::Included(v) => v.clone(),
+                    NumericBound::Excluded(v) => format!("({}", v),
+                    NumericBound::Unbounded => "+inf".to_string(),
+                };
+                out.push_str(&format!("@{}:[{} {}]", numeric.field, min, max));
+            }
+            QueryNode::Tag(tag) => {
+                out.push_str(&format!("@{}:{{ ", tag.field));
+                for (i, value) in tag.values.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(" | ");
+                    }
+                    value.render(out);
+                }
+                out.push_str(" }");
+            }
+            QueryNode::Text(text) => {
+                out.push_str(&format!("@{}:", text.field));
+                if text.values.len() > 1 {
+                    out.push('(');
+                }
+                for (i, value) in text.values.iter().enumerate() {
+                    if i > 0 {
+                        out.push('|');
+                    }
+                    value.render(out);
+                }
+                if text.values.len() > 1 {
+                    out.push(')');
+                }
+            }
+            QueryNode::Geo(geo) => match &geo.params {
+                Some([lon, lat, radius]) => out.push_str(&format!(
+                    "@{}:[${} ${} ${} {}]",
+                    geo.field,
+                    lon,
+                    lat,
+                    radius,
+                    geo.unit.as_str()
+                )),
+                None => out.push_str(&format!(
+                    "@{}:[{} {} {} {}]",
+                    geo.field,
+                    geo.lon,
+                    geo.lat,
+                    geo.radius,
+                    geo.unit.as_str()
+                )),
+            },
+            QueryNode::All => out.push('*'),
+            QueryNode::Knn(knn) => {
+                match &knn.filter {
+                    Some(filter) => {
+                        out.push('(');
+                        filter.render(out);
+                        out.push(')');
+                    }
+                    None => out.push('*'),
+                }
+                out.push_str(&format!("=>[KNN {} @{} ${}]", knn.k, knn.field, knn.param));
+            }
+        }
+    }
+
+    fn render_grouped(&self, out: &mut String, grouped: bool) {
+        if grouped {
+            out.push('(');
+            self.render(out);
+            out.push(')');
+        } else {
+            self.render(out);
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Expr {
+    pub(crate) root: Option<QueryNode>,
+    pub(crate) params: BTreeMap<String, Bytes>,
+    pub(crate) error: Option<crate::Error>,
+    pub(crate) option: FTSearchOption,
+}
+
+impl From<QueryNode> for Expr {
+    fn from(node: QueryNode) -> Self {
+        Expr {
+            root: Some(node),
+            ..Default::default()
+        }
+    }
+}
+
+impl Expr {
+    pub fn all() -> Expr {
+        QueryNode::All.into()
+    }
+
+    pub fn root(&self) -> Option<&QueryNode> {
+        self.root.as_ref()
+    }
+
+    pub fn root_mut(&mut self) -> Option<&mut QueryNode> {
+        self.root.as_mut()
+    }
+
+    pub fn ft_search(&self, index: &str) -> crate::Result<FTSearch> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+
+        let query = match &self.root {
+            Some(root) => root.to_query(),
+            None => return Err(crate::Error::EmptyQueryBody),
+        };
+
+        let mut option = self.option.clone();
+        option.params = Some(
+            self.params
+                .iter()
+                .map(|(k, v)| FTSearchParam {
+                    name: k.clone(),
+                    value: v.clone(),
+                })
+                .collect_vec(),
+        );
+
+        Ok(FTSearch {
+            query,
+            index: index.to_string(),
+            option,
+        })
+    }
+
+    fn search_option_mut(&mut self) -> &mut FTSearchOption {
+        &mut self.option
+    }
+
+    /// Binds `$name` to `value` through PARAMS, which needs DIALECT 2.
+    pub(crate) fn with_param(mut self, name: &str, value: &[u8]) -> Expr {
+        if let Err(err) = check_param_name(name) {
+            return Expr {
+                error: Some(err),
+                ..Default::default()
+            };
+        }
+        self.params
+            .insert(name.to_string(), Bytes::copy_from_slice(value));
+        self.dialect(2)
+    }
+
+    /// Moves the values of numeric, exact tag and geo predicates to PARAMS as
+    /// `$p0`, `$p1`..., skipping names already bound, and sets DIALECT 2.
+    pub fn parameterize(mut self) -> Expr {
+        if self.error.is_some() {
+            return self;
+        }
+        let Some(root) = self.root.as_mut() else {
+            return self;
+        };
+
+        let params = &mut self.params;
+        let mut next = 0;
+        let mut bind = |value: String| {
+            let name = loop {
+                let name = format!("p{}", next);
+                next += 1;
+                if !params.contains_key(&name) {
+                    break name;
+                }
+            };
+            params.insert(name.clone(), Bytes::from(value));
+            name
+        };
+        root.walk_mut(&mut |node| match node {
+            QueryNode::Numeric(numeric) => {
+                // Equal bounds, as in `eq`, share a single param.
+                let mut last: Option<(String, String)> = None;
+                for bound in [&mut numeric.min, &mut numeric.max] {
+                    if let NumericBound::Included(v) | NumericBound::Excluded(v) = bound {
+                        // Numbers never start with `$`, so this is already bound.
+                        if !v.starts_with('$') {
+                            let name = match last {
+                                Some((value, name)) if value == *v => name,
+                                _ => bind(v.clone()),
+                            };
+                            last = Some((v.clone(), name.clone()));
+                            *v = format!("${}", name);
+                        }
+                    }
+                }
+            }
+            QueryNode::Tag(tag) => {
+                for value in tag.values.iter_mut() {
+                    if let TagValue::Exact(v) = value {
+                        *value = TagValue::Param(bind(v.clone()));
+                    }
+                }
+            }
+            QueryNode::Geo(geo) if geo.params.is_none() => {
+                geo.params = Some([
+                    bind(geo.lon.to_string()),
+                    bind(geo.lat.to_string()),
+                    bind(geo.radius.to_string()),
+                ]);
+            }
+            _ => {}
+        });
+
+        self.dialect(2)
+    }
+
+    /// Marks this expression as optional: documents matching it rank higher,
+    /// but it no longer filters.
+    pub fn optional(self) -> Expr {
+        if self.error.is_some() {
+            return self;
+        }
+        match self.root {
+            // The KNN clause always applies to the whole query.
+            Some(QueryNode::Knn(_)) => Expr {
+                error: Some(crate::Error::InvalidQuery(
+                    "a KNN query can't be optional".to_string(),
+                )),
+                ..Default::default()
+            },
+            root => Expr {
+                root: root.map(|node| QueryNode::Optional(Box::new(node))),
+                ..self
+            },
+        }
+    }
+
+    /// Attaches `attributes` to this expression, or to the filter of a KNN
+    /// query. Attributes that don't apply to the fields queried are an error.
+    pub fn with_attributes(self, attributes: QueryAttributes) -> Expr {
+        if self.error.is_some() {
+            return self;
+        }
+        let (filter, knn) = split_knn(self.root);
+        let filter = match filter {
+            Some(node) => match attributes.validate(&node) {
+                Ok(()) => Some(QueryNode::Attributed(Box::new(node), attributes)),
+                Err(err) => {
+                    return Expr {
+                        error: Some(crate::Error::InvalidQuery(err)),
+                        ..Default::default()
+                    }
+                }
+            },
+            None => {
+                return Expr {
+                    error: Some(crate::Error::InvalidQuery(
+                        "attributes need a query to apply to, not a bare KNN".to_string(),
+                    )),
+                    ..Default::default()
+                }
+            }
+        };
+        let root = match knn {
+            Some(mut knn) => {
+                knn.filter = filter.map(Box::new);
+                Some(QueryNode::Knn(knn))
+            }
+            None => filter,
+        };
+        Expr { root, ..self }
+    }
+
+    pub fn and(self, other: Expr) -> Expr {
+        self.combine(other, QueryNode::and)
+    }
+
+    /// Only `and` is defined for a KNN query, whose filter is the other side.
+    pub fn or(self, other: Expr) -> Expr {
+        let is_knn = |expr: &Expr| matches!(expr.root, Some(QueryNode::Knn(_)));
+        if self.error.is_none() && other.error.is_none() && (is_knn(&self) || is_knn(&other)) {
+            return Expr {
+                error: Some(crate::Error::InvalidQuery(
+                    "a KNN query can't be OR-ed".to_string(),
+                )),
+                ..Default::default()
+            };
+        }
+        self.combine(other, QueryNode::or)
+    }
+
+    fn combine<F>(mut self, mut other: Expr, f: F) -> Expr
+    where
+        F: FnOnce(QueryNode, QueryNode) -> QueryNode,
+    {
+        if self.error.is_some() {
+            return Expr {
+                error: self.error,
+                ..Default::default()
+            };
+        }
+        if other.error.is_some() {
+            return Expr {
+                error: other.error,
+                ..Default::default()
+            };
+        }
+
+        // Params of `other` clashing with a different value are renamed,
+        // identical ones are shared.
+        let mut renames = BTreeMap::new();
+        for (name, value) in &other.params {
+            if self.params.get(name).is_some_and(|x| x != value) {
+                let renamed = (1..)
+                    .map(|i| format!("{}_{}", name, i))
+                    .find(|x| {
+                        !self.params.contains_key(x)
+                            && !other.params.contains_key(x)
+                            && !renames.values().any(|y| y == x)
+                    })
+                    .unwrap();
+                renames.insert(name.clone(), renamed);
+            }
+        }
+        if let Some(root) = other.root.as_mut() {
+            root.rename_params(&renames);
+        }
+        for (name, value) in other.params {
+            let name = renames.get(&name).cloned().unwrap_or(name);
+            self.params.insert(name, value);
+        }
+
+        let (left, left_knn) = split_knn(self.root);
+        let (right, right_knn) = split_knn(other.root);
+
+        if left_knn.is_some() && right_knn.is_some() {
+            return Expr {
+                error: Some(crate::Error::DuplicatedVectorQuery),
+                ..Default::default()
+            };
+        }
+
+        let filter = match (left, right) {
+            (Some(left), Some(right)) => Some(f(left, right)),
+            (left, right) => left.or(right),
+        };
+
+        let root = match left_knn.or(right_knn) {
+            Some(mut knn) => {
+                knn.filter = filter.map(Box::new);
+                Some(QueryNode::Knn(knn))
+            }
+            None => filter,
+        };
+
+        Expr {
+            root,
+            params: self.params,
+            error: None,
+            option: self.option.merge(other.option),
+        }
+    }
+}
+
+impl_search_option!(Expr);
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        if self.error.is_some() {
+            return self;
+        }
+        match self.root {
+            Some(QueryNode::Knn(_)) => Expr {
+                error: Some(crate::Error::InvalidQuery(
+                    "a KNN query can't be negated".to_string(),
+                )),
+                ..Default::default()
+            },
+            Some(QueryNode::Not(node)) => Expr {
+                root: Some(*node),
+                ..self
+            },
+            root => Expr {
+                root: root.map(|node| QueryNode::Not(Box::new(node))),
+                ..self
+            },
+        }
+    }
+}
+
+fn split_knn(root: Option<QueryNode>) -> (Option<QueryNode>, Option<KnnQuery>) {
+    match root {
+        Some(QueryNode::Knn(mut knn)) => (knn.filter.take().map(|x| *x), Some(knn)),
+        root => (root, None),
+    }
+}
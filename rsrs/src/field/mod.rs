@@ -6,188 +6,33 @@ pub use number_field::*;
 pub use text_field::*;
 pub use vector_field::*;
 
-use std::collections::HashMap;
-
-use bytes::Bytes;
-use itertools::Itertools;
+use std::ops::Not;
 
 use crate::{
-    query::{FTSearch, FTSearchOption, FTSearchParam},
-    SchemaField,
+    CoordSystem, Expr, FieldKind, GeoOptions, GeoPredicate, GeoShapeOptions, GeoUnit, QueryNode,
+    SchemaField, Sortable, TagOptions, TagPredicate, TagValue,
 };
 
-#[derive(Debug)]
-pub enum SortOrder {
-    ASC,
-    DESC,
-}
+pub trait Field {
+    fn field_name(&self) -> &'static str;
 
-#[derive(Default, Debug)]
-pub struct Expr {
-    filter: String,
-    knn_query: Option<String>,
-    params: HashMap<String, Bytes>,
-    error: Option<crate::Error>,
-    dialect: Option<usize>,
-    sort_by: Option<(&'static str, SortOrder)>,
+    fn to_schema_fields(&self) -> SchemaField;
 }
 
-impl Expr {
-    pub fn ft_search(&self, index: &str) -> crate::Result<FTSearch> {
-        if let Some(err) = self.error {
-            return Err(err);
-        }
-
-        let query = match (&self.knn_query, self.filter.len()) {
-            (Some(knn_query), 0) => {
-                format!("*=>{}", knn_query)
-            }
-            (Some(knn_query), u) if u > 0 => {
-                format!("({})=>{}", self.filter, knn_query)
-            }
-            (None, u) if u > 0 => self.filter.clone(),
-            _ => return Err(crate::Error::EmptyQueryBody),
-        };
-
-        Ok(FTSearch {
-            query,
-            index: index.to_string(),
-            option: FTSearchOption {
-                returns: None,
-                params: Some(
-                    self.params
-                        .iter()
-                        .map(|(k, v)| FTSearchParam {
-                            name: k.clone(),
-                            value: v.clone(),
-                        })
-                        .collect_vec(),
-                ),
-                sortby: None,
-                dialect: self.dialect,
-            },
-        })
-    }
-
-    pub fn sort_by<F: Field>(self, field: F, order: SortOrder) -> Expr {
-        Expr {
-            sort_by: Some((field.field_name(), order)),
-            ..self
-        }
-    }
-
-    pub fn and(mut self, other: Expr) -> Expr {
-        if self.error.is_some() {
-            return Expr {
-                error: self.error,
-                ..Default::default()
-            };
-        }
-        if other.error.is_some() {
-            return Expr {
-                error: other.error,
-                ..Default::default()
-            };
-        }
-
-        if self.knn_query.is_some() && other.knn_query.is_some() {
-            return Expr {
-                error: Some(crate::Error::DuplicatedVectorQuery),
-                ..Default::default()
-            };
-        }
-
-        let count_params = self.params.len() + other.params.len();
-
-        self.params.extend(other.params);
-
-        if self.params.len() < count_params {
-            return Expr {
-                error: Some(crate::Error::DuplicatedParam),
-                ..Default::default()
-            };
-        }
-
-        let filter = match (self.filter.len(), other.filter.len()) {
-            (0, 0) => String::new(),
-            (0, x) if x > 0 => other.filter,
-            (x, 0) if x > 0 => self.filter,
-            (_, _) => format!("{} {}", self.filter, other.filter),
-        };
-
-        Expr {
-            filter,
-            knn_query: self.knn_query.or(other.knn_query),
-            params: self.params,
-            error: None,
-            ..Default::default()
-        }
-    }
-
-    pub fn or(mut self, other: Expr) -> Expr {
-        if self.error.is_some() {
-            return Expr {
-                error: self.error,
-                ..Default::default()
-            };
-        }
-        if other.error.is_some() {
-            return Expr {
-                error: other.error,
-                ..Default::default()
-            };
-        }
-
-        if self.knn_query.is_some() && other.knn_query.is_some() {
-            return Expr {
-                error: Some(crate::Error::DuplicatedVectorQuery),
-                ..Default::default()
-            };
-        }
-
-        let count_params = self.params.len() + other.params.len();
-
-        self.params.extend(other.params);
-
-        if self.params.len() < count_params {
-            return Expr {
-                error: Some(crate::Error::DuplicatedParam),
-                ..Default::default()
-            };
-        }
-
-        let filter = match (self.filter.len(), other.filter.len()) {
-            (0, 0) => String::new(),
-            (0, x) if x > 0 => other.filter,
-            (x, 0) if x > 0 => self.filter,
-            (_, _) => format!("({})|({})", self.filter, other.filter),
-        };
-
-        Expr {
-            filter,
-            knn_query: self.knn_query.or(other.knn_query),
-            params: self.params,
-            error: None,
-            ..Default::default()
-        }
+impl<F: Field + ?Sized> Field for &F {
+    fn field_name(&self) -> &'static str {
+        (**self).field_name()
     }
 
-    pub fn dialect(self, dialect: usize) -> Expr {
-        Expr {
-            dialect: Some(dialect),
-            ..self
-        }
+    fn to_schema_fields(&self) -> SchemaField {
+        (**self).to_schema_fields()
     }
 }
 
-pub trait Field {
-    fn field_name(&self) -> &'static str;
-
-    fn to_schema_fields(&self) -> SchemaField;
-}
-
 pub struct GeoField {
     pub field_name: &'static str,
+    pub sortable: Option<bool>,
+    pub no_index: Option<bool>,
 }
 
 impl Field for GeoField {
@@ -199,27 +44,46 @@ impl Field for GeoField {
         SchemaField {
             identifier: self.field_name.to_string(),
             attribute: None,
-            field_type: "GEO",
-            options: Default::default(),
+            kind: FieldKind::Geo(GeoOptions {
+                sortable: self.sortable.unwrap_or_default(),
+                no_index: self.no_index.unwrap_or_default(),
+            }),
         }
     }
 }
 
 impl GeoField {
     pub fn new(name: &'static str) -> Self {
-        Self { field_name: name }
+        Self {
+            field_name: name,
+            sortable: None,
+            no_index: None,
+        }
     }
 
-    pub fn query(&self, lon: f32, lat: f32, radius: usize, unit: &str) -> Expr {
-        Expr {
-            filter: format!("@{}:[{} {} {} {}]", self.field_name, lon, lat, radius, unit),
-            ..Default::default()
-        }
+    pub fn query(&self, lon: f32, lat: f32, radius: usize, unit: GeoUnit) -> Expr {
+        QueryNode::Geo(GeoPredicate {
+            field: self.field_name,
+            lon,
+            lat,
+            radius,
+            unit,
+            params: None,
+        })
+        .into()
     }
 }
 
 pub struct TagField {
     pub field_name: &'static str,
+    pub separator: Option<char>,
+    pub case_sensitive: Option<bool>,
+    pub with_suffix_trie: Option<bool>,
+    pub index_empty: Option<bool>,
+    pub index_missing: Option<bool>,
+    pub sortable: Option<bool>,
+    pub unf: Option<bool>,
+    pub no_index: Option<bool>,
 }
 
 impl Field for TagField {
@@ -231,25 +95,125 @@ impl Field for TagField {
         SchemaField {
             identifier: self.field_name.to_string(),
             attribute: None,
-            field_type: "TAG",
-            options: Default::default(),
+            kind: FieldKind::Tag(TagOptions {
+                separator: self.separator,
+                case_sensitive: self.case_sensitive.unwrap_or_default(),
+                with_suffix_trie: self.with_suffix_trie.unwrap_or_default(),
+                index_empty: self.index_empty.unwrap_or_default(),
+                index_missing: self.index_missing.unwrap_or_default(),
+                sortable: Sortable::new(
+                    self.sortable.unwrap_or_default(),
+                    self.unf.unwrap_or_default(),
+                ),
+                no_index: self.no_index.unwrap_or_default(),
+            }),
         }
     }
 }
 
 impl TagField {
     pub fn new(name: &'static str) -> Self {
-        Self { field_name: name }
+        Self {
+            field_name: name,
+            separator: None,
+            case_sensitive: None,
+            with_suffix_trie: None,
+            index_empty: None,
+            index_missing: None,
+            sortable: None,
+            unf: None,
+            no_index: None,
+        }
+    }
+
+    fn query(&self, values: Vec<TagValue>) -> Expr {
+        QueryNode::Tag(TagPredicate {
+            field: self.field_name,
+            values,
+        })
+        .into()
     }
 
+    pub fn eq<T: AsRef<str>>(&self, value: T) -> Expr {
+        self.contains(&[value])
+    }
+
+    pub fn ne<T: AsRef<str>>(&self, value: T) -> Expr {
+        self.eq(value).not()
+    }
+
+    /// Like [`TagField::eq`], with `value` sent through PARAMS as `$name`
+    /// instead of the query text.
+    pub fn eq_param(&self, name: &str, value: &str) -> Expr {
+        self.query(vec![TagValue::Param(name.to_string())])
+            .with_param(name, value.as_bytes())
+    }
+
+    /// Matches documents having any of `values`.
     pub fn contains<T: AsRef<str>>(&self, values: &[T]) -> Expr {
-        Expr {
-            filter: format!(
-                "@{}:{{ {} }}",
-                self.field_name,
-                values.iter().map(|x| x.as_ref()).join(" | ")
-            ),
-            ..Default::default()
+        if values.is_empty() || values.iter().any(|x| x.as_ref().is_empty()) {
+            return Expr {
+                error: Some(crate::Error::InvalidQuery(format!(
+                    "empty tag for `{}`",
+                    self.field_name
+                ))),
+                ..Default::default()
+            };
+        }
+        self.query(
+            values
+                .iter()
+                .map(|x| TagValue::Exact(x.as_ref().to_string()))
+                .collect(),
+        )
+    }
+
+    pub fn not_contains<T: AsRef<str>>(&self, values: &[T]) -> Expr {
+        self.contains(values).not()
+    }
+
+    pub fn prefix(&self, prefix: &str) -> Expr {
+        self.query(vec![TagValue::Prefix(prefix.to_string())])
+    }
+
+    /// Matches tags ending with `suffix`, which a tag field indexed
+    /// WITHSUFFIXTRIE answers without scanning every tag.
+    pub fn suffix(&self, suffix: &str) -> Expr {
+        self.query(vec![TagValue::Suffix(suffix.to_string())])
+    }
+
+    /// Matches tags containing `infix`, see [`TagField::suffix`].
+    pub fn infix(&self, infix: &str) -> Expr {
+        self.query(vec![TagValue::Infix(infix.to_string())])
+    }
+
+    /// Matches whole tags against a `*`/`?` pattern. Tag wildcards need
+    /// DIALECT 2.
+    pub fn wildcard(&self, pattern: &str) -> Expr {
+        self.query(vec![TagValue::Wildcard(pattern.to_string())])
+            .dialect(2)
+    }
+}
+
+pub struct GeoShapeField {
+    pub field_name: &'static str,
+    pub coord_system: Option<CoordSystem>,
+    pub no_index: Option<bool>,
+}
+
+impl Field for GeoShapeField {
+    fn field_name(&self) -> &'static str {
+        self.field_name
+    }
+
+    fn to_schema_fields(&self) -> SchemaField {
+        SchemaField {
+            identifier: self.field_name.to_string(),
+            attribute: None,
+            kind: FieldKind::GeoShape(GeoShapeOptions {
+                coord_system: self.coord_system,
+                no_index: self.no_index.unwrap_or_default(),
+            }),
         }
     }
 }
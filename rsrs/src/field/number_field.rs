@@ -1,6 +1,6 @@
-use std::ops::RangeBounds;
+use std::ops::{Not, RangeBounds};
 
-use crate::{Expr, Field, SchemaField};
+use crate::{Expr, Field, NumericBound, NumericPredicate, QueryNode, SchemaField};
 
 pub trait NumberField: Field {
     type Number;
@@ -12,12 +12,50 @@ pub trait NumberField: Field {
     fn ne(&self, number: Self::Number) -> Expr;
 }
 
+/// Renders a bound of a numeric range, the way RediSearch parses it.
+trait NumericLiteral {
+    fn literal(&self) -> crate::Result<String>;
+}
+
+macro_rules! impl_numeric_literal_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl NumericLiteral for $ty {
+                fn literal(&self) -> crate::Result<String> {
+                    Ok(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_numeric_literal_integer!(usize, u8, u16, u32, u64, u128, isize, i8, i16, i32, i64, i128);
+
+macro_rules! impl_numeric_literal_float {
+    ($($ty:ty),*) => {
+        $(
+            impl NumericLiteral for $ty {
+                fn literal(&self) -> crate::Result<String> {
+                    if self.is_nan() {
+                        Err(crate::Error::InvalidQuery("NaN is not a numeric bound".to_string()))
+                    } else if self.is_infinite() {
+                        Ok(if *self > 0. { "+inf" } else { "-inf" }.to_string())
+                    } else {
+                        Ok(self.to_string())
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_numeric_literal_float!(f32, f64);
+
 macro_rules! impl_number_field {
     ($ty:ty, $name:ident) => {
         pub struct $name {
             pub field_name: &'static str,
             pub sortable: bool,
-            pub unf: bool,
             pub no_index: bool,
         }
 
@@ -30,51 +68,57 @@ macro_rules! impl_number_field {
                 SchemaField {
                     identifier: self.field_name.to_string(),
                     attribute: None,
-                    field_type: "NUMERIC",
-                    options: crate::create::FieldOption {
-                        sortable: Some(self.sortable),
-                        unf: Some(self.unf),
-                        noindex: Some(self.no_index),
-                        ..Default::default()
-                    },
+                    kind: crate::FieldKind::Numeric(crate::NumericOptions {
+                        sortable: self.sortable,
+                        no_index: self.no_index,
+                    }),
                 }
             }
         }
 
+        impl From<&$name> for crate::aggregate::ApplyExpr<crate::aggregate::apply::Num> {
+            fn from(field: &$name) -> Self {
+                crate::aggregate::ApplyExpr::property(field.field_name)
+            }
+        }
+
+        crate::aggregate::apply::impl_field_arithmetic!($name);
+
         impl NumberField for $name {
             type Number = $ty;
 
             fn in_range<T: RangeBounds<Self::Number>>(&self, range: T) -> Expr {
-                let start = match range.start_bound() {
-                    core::ops::Bound::Included(start) => format!("{}", start),
-                    core::ops::Bound::Excluded(start) => format!("({}", start),
-                    core::ops::Bound::Unbounded => "-inf".to_string(),
+                let bound = |bound: core::ops::Bound<&$ty>| {
+                    Ok(match bound {
+                        core::ops::Bound::Included(x) => NumericBound::Included(x.literal()?),
+                        core::ops::Bound::Excluded(x) => NumericBound::Excluded(x.literal()?),
+                        core::ops::Bound::Unbounded => NumericBound::Unbounded,
+                    })
                 };
-
-                let end = match range.end_bound() {
-                    core::ops::Bound::Included(end) => format!("{}", end),
-                    core::ops::Bound::Excluded(end) => format!("({}", end),
-                    core::ops::Bound::Unbounded => "+inf".to_string(),
+                let (min, max) = match (bound(range.start_bound()), bound(range.end_bound())) {
+                    (Ok(min), Ok(max)) => (min, max),
+                    (Err(err), _) | (_, Err(err)) => {
+                        return Expr {
+                            error: Some(err),
+                            ..Default::default()
+                        }
+                    }
                 };
 
-                Expr {
-                    filter: format!("@{}:[{} {}]", self.field_name(), start, end),
-                    ..Default::default()
-                }
+                QueryNode::Numeric(NumericPredicate {
+                    field: self.field_name(),
+                    min,
+                    max,
+                })
+                .into()
             }
 
             fn eq(&self, number: Self::Number) -> Expr {
-                Expr {
-                    filter: format!("@{}:[{} {}]", self.field_name(), number, number),
-                    ..Default::default()
-                }
+                self.in_range(number..=number)
             }
 
             fn ne(&self, number: Self::Number) -> Expr {
-                return Expr {
-                    filter: format!("-@{}:[{} {}]", self.field_name(), number, number),
-                    ..Default::default()
-                };
+                self.eq(number).not()
             }
         }
     };
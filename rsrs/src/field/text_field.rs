@@ -1,6 +1,8 @@
-use itertools::Itertools;
+use std::ops::Not;
 
-use crate::{Expr, Field, SchemaField};
+use crate::{
+    Expr, Field, FieldKind, QueryNode, SchemaField, Sortable, TextOptions, TextPredicate, TextValue,
+};
 
 pub struct TextField {
     pub field_name: &'static str,
@@ -8,6 +10,7 @@ pub struct TextField {
     pub no_stem: Option<bool>,
     pub phonetic: Option<PhoneticMatcher>,
     pub sortable: Option<bool>,
+    pub unf: Option<bool>,
     pub no_index: Option<bool>,
     pub with_suffix_trie: Option<bool>,
 }
@@ -18,53 +21,117 @@ impl Field for TextField {
     }
 
     fn to_schema_fields(&self) -> SchemaField {
-        let mut options = vec![];
-        if let Some(weight) = self.weight {
-            options.push("WEIGHT".to_owned());
-            options.push(format!("{}", weight));
-        }
         SchemaField {
             identifier: self.field_name.to_string(),
             attribute: None,
-            field_type: "TEXT",
-            options: crate::create::FieldOption {
+            kind: FieldKind::Text(TextOptions {
+                no_stem: self.no_stem.unwrap_or_default(),
                 weight: self.weight,
-                nostem: self.no_stem,
                 phonetic: self.phonetic,
-                sortable: self.sortable,
-                noindex: self.no_index,
-                // with_suffix_trie: self.with_suffix_trie,
-                ..Default::default()
-            },
+                with_suffix_trie: self.with_suffix_trie.unwrap_or_default(),
+                sortable: Sortable::new(
+                    self.sortable.unwrap_or_default(),
+                    self.unf.unwrap_or_default(),
+                ),
+                no_index: self.no_index.unwrap_or_default(),
+            }),
         }
     }
 }
 
 impl TextField {
+    fn query(&self, values: Vec<TextValue>) -> Expr {
+        QueryNode::Text(TextPredicate {
+            field: self.field_name,
+            values,
+            phonetic: self.phonetic.is_some(),
+        })
+        .into()
+    }
+
+    /// Matches a single term, subject to stemming.
     pub fn eq<T: AsRef<str>>(&self, value: T) -> Expr {
         self.contains(&[value])
     }
 
+    pub fn ne<T: AsRef<str>>(&self, value: T) -> Expr {
+        self.eq(value).not()
+    }
+
+    /// Like [`TextField::eq`], with `value` sent through PARAMS as `$name`
+    /// instead of the query text.
+    pub fn eq_param(&self, name: &str, value: &str) -> Expr {
+        self.query(vec![TextValue::Param(name.to_string())])
+            .with_param(name, value.as_bytes())
+    }
+
+    /// Matches any of the terms, rendered as a union `(a|b)`.
     pub fn contains<T: AsRef<str>>(&self, values: &[T]) -> Expr {
-        Expr {
-            filter: format!(
-                "@{}:{{ {} }}",
-                self.field_name,
-                values.iter().map(|x| x.as_ref()).join(" | ")
-            ),
-            ..Default::default()
+        if values.is_empty() || values.iter().any(|x| x.as_ref().is_empty()) {
+            return Expr {
+                error: Some(crate::Error::InvalidQuery(format!(
+                    "empty term for `{}`",
+                    self.field_name
+                ))),
+                ..Default::default()
+            };
         }
+        self.query(
+            values
+                .iter()
+                .map(|x| TextValue::Term(x.as_ref().to_string()))
+                .collect(),
+        )
     }
 
     pub fn not_contains<T: AsRef<str>>(&self, values: &[T]) -> Expr {
-        Expr {
-            filter: format!(
-                "-@{}:{{ {} }}",
-                self.field_name,
-                values.iter().map(|x| x.as_ref()).join(" | ")
-            ),
-            ..Default::default()
+        self.contains(values).not()
+    }
+
+    /// Matches the exact phrase `"..."`.
+    pub fn phrase(&self, phrase: &str) -> Expr {
+        self.query(vec![TextValue::Phrase(phrase.to_string())])
+    }
+
+    pub fn prefix(&self, prefix: &str) -> Expr {
+        self.query(vec![TextValue::Prefix(prefix.to_string())])
+    }
+
+    /// Matches terms ending with `suffix`. Without WITHSUFFIXTRIE on the
+    /// field, every term of the dictionary is scanned.
+    pub fn suffix(&self, suffix: &str) -> Expr {
+        self.query(vec![TextValue::Suffix(suffix.to_string())])
+    }
+
+    /// Matches terms containing `infix`, see [`TextField::suffix`].
+    pub fn infix(&self, infix: &str) -> Expr {
+        self.query(vec![TextValue::Infix(infix.to_string())])
+    }
+
+    /// Matches terms within a Levenshtein `distance` of 1 to 3.
+    pub fn fuzzy(&self, term: &str, distance: usize) -> Expr {
+        if !(1..=3).contains(&distance) {
+            return Expr {
+                error: Some(crate::Error::InvalidQuery(format!(
+                    "fuzzy distance must be between 1 and 3, got {}",
+                    distance
+                ))),
+                ..Default::default()
+            };
         }
+        self.query(vec![TextValue::Fuzzy(term.to_string(), distance)])
+    }
+
+    /// Matches `term` if present, ranking those documents higher.
+    pub fn optional(&self, term: &str) -> Expr {
+        self.eq(term).optional()
+    }
+
+    /// Matches single terms against a `*`/`?` pattern, quoted as `w'...'`,
+    /// which only DIALECT 2 parses.
+    pub fn wildcard(&self, pattern: &str) -> Expr {
+        self.query(vec![TextValue::Wildcard(pattern.to_string())])
+            .dialect(2)
     }
 }
 
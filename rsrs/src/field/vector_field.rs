@@ -1,9 +1,12 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use bytes::Bytes;
 use redis::ToRedisArgs;
 
-use crate::{Expr, Field, SchemaField};
+use crate::{
+    expr::check_param_name, Expr, Field, FieldKind, KnnQuery, QueryNode, SchemaField, VectorIndex,
+    VectorOptions,
+};
 
 pub trait VectorField: Field {
     type Number;
@@ -36,16 +39,22 @@ pub enum DistanceMetric {
     COSINE,
 }
 
+impl DistanceMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::L2 => "L2",
+            Self::IP => "IP",
+            Self::COSINE => "COSINE",
+        }
+    }
+}
+
 impl ToRedisArgs for DistanceMetric {
     fn write_redis_args<W>(&self, out: &mut W)
     where
         W: ?Sized + redis::RedisWrite,
     {
-        match self {
-            Self::L2 => "L2".write_redis_args(out),
-            Self::IP => "IP".write_redis_args(out),
-            Self::COSINE => "COSINE".write_redis_args(out),
-        }
+        self.as_str().write_redis_args(out)
     }
 }
 
@@ -61,7 +70,7 @@ macro_rules! impl_vector_field {
             pub m: Option<usize>,
             pub ef_construction: Option<usize>,
             pub ef_runtime: Option<usize>,
-            pub epsilon: Option<usize>,
+            pub epsilon: Option<f32>,
         }
 
         impl Field for $name {
@@ -70,31 +79,27 @@ macro_rules! impl_vector_field {
             }
 
             fn to_schema_fields(&self) -> SchemaField {
-                let count = 6 + 2
-                    * (self.initial_cap.is_some() as u8
-                        + self.block_size.is_some() as u8
-                        + self.m.is_some() as u8
-                        + self.ef_construction.is_some() as u8
-                        + self.ef_runtime.is_some() as u8
-                        + self.epsilon.is_some() as u8);
-                SchemaField {
-                    identifier: self.field_name.to_string(),
-                    attribute: None,
-                    field_type: "VECTOR",
-                    options: crate::create::FieldOption {
-                        dim: Some(self.dim),
-                        algorithm: Some(self.algorithm),
-                        count: Some(count as usize),
-                        distance_metric: Some(self.distance_metric),
-                        initial_cap: self.initial_cap,
-                        vector_type: Some(VectorType::$vector_type),
+                let index = match self.algorithm {
+                    VectorAlgorithm::FLAT => VectorIndex::Flat {
                         block_size: self.block_size,
+                    },
+                    VectorAlgorithm::HNSW => VectorIndex::Hnsw {
                         m: self.m,
                         ef_construction: self.ef_construction,
                         ef_runtime: self.ef_runtime,
                         epsilon: self.epsilon,
-                        ..Default::default()
                     },
+                };
+                SchemaField {
+                    identifier: self.field_name.to_string(),
+                    attribute: None,
+                    kind: FieldKind::Vector(VectorOptions {
+                        index,
+                        vector_type: VectorType::$vector_type,
+                        dim: self.dim,
+                        distance_metric: self.distance_metric,
+                        initial_cap: self.initial_cap,
+                    }),
                 }
             }
         }
@@ -108,14 +113,26 @@ macro_rules! impl_vector_field {
                 topk: usize,
                 param_name: Option<&str>,
             ) -> Expr {
-                let mut params = HashMap::new();
+                let param = param_name.unwrap_or("vec").to_string();
+                if let Err(err) = check_param_name(&param) {
+                    return Expr {
+                        error: Some(err),
+                        ..Default::default()
+                    };
+                }
+                let mut params = BTreeMap::new();
 
-                let embeddingbytes: &[u8] = bytemuck::cast_slice(embedding);
-                let param = format!("${}", param_name.unwrap_or("vec"));
-                params.insert(param.clone(), Bytes::copy_from_slice(embeddingbytes));
+                let embeddingbytes: Vec<u8> =
+                    embedding.iter().flat_map(|x| x.to_le_bytes()).collect();
+                params.insert(param.clone(), Bytes::from(embeddingbytes));
 
                 Expr {
-                    knn_query: Some(format!("[KNN {} @{} {}]", topk, self.field_name, param)),
+                    root: Some(QueryNode::Knn(KnnQuery {
+                        filter: None,
+                        k: topk,
+                        field: self.field_name,
+                        param,
+                    })),
                     params,
                     ..Default::default()
                 }
@@ -127,20 +144,26 @@ macro_rules! impl_vector_field {
 impl_vector_field!(f32, VectorFieldF32, Float32);
 impl_vector_field!(f64, VectorFieldF64, Float64);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum VectorType {
     Float32,
     Float64,
 }
 
+impl VectorType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Float32 => "FLOAT32",
+            Self::Float64 => "FLOAT64",
+        }
+    }
+}
+
 impl ToRedisArgs for VectorType {
     fn write_redis_args<W>(&self, out: &mut W)
     where
         W: ?Sized + redis::RedisWrite,
     {
-        match self {
-            Self::Float32 => "FLOAT32".write_redis_args(out),
-            Self::Float64 => "FLOAT64".write_redis_args(out),
-        }
+        self.as_str().write_redis_args(out)
     }
 }
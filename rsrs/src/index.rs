@@ -0,0 +1,182 @@
+use std::marker::PhantomData;
+
+use redis::{aio, ConnectionLike, FromRedisValue};
+
+use crate::{
+    aggregate::{AggregateResults, FTAggregateBuilder},
+    AsyncSearchCommands, DataType, DecodeDocument, Document, Expr, FTCreate, IndexInfo,
+    IndexOption, SchemaField, SearchCommands, SearchResults,
+};
+
+/// A search index over documents of type `T`.
+///
+/// The name, options and schema are resolved once, so every command run
+/// through the handle targets the same index with the same document type.
+#[derive(Debug)]
+pub struct Index<T> {
+    create: FTCreate,
+    _doc: PhantomData<fn() -> T>,
+}
+
+impl<T: Document> Index<T> {
+    /// The index declared by `#[document(...)]` on `T`.
+    pub fn declared() -> crate::Result<Self> {
+        Index::new(T::index_name(), T::index_option())
+    }
+
+    /// Fails with [`crate::Error::InvalidFilter`] if the FILTER of the index
+    /// does not validate, and with [`crate::Error::InvalidQuery`] if `option`
+    /// overrides the data type the hits of `T` are decoded from.
+    pub fn new(name: &str, option: IndexOption) -> crate::Result<Self> {
+        let create = T::create_index(name, option);
+        create.validate()?;
+        let is_json = |on: &Option<DataType>| matches!(on, Some(DataType::Json));
+        if is_json(&create.option.on) != is_json(&T::index_option().on) {
+            return Err(crate::Error::InvalidQuery(format!(
+                "index `{}` is not ON {} like its document type",
+                name,
+                T::index_option().on.unwrap_or(DataType::Hash).as_str()
+            )));
+        }
+        Ok(Index {
+            create,
+            _doc: PhantomData,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.create.name
+    }
+
+    pub fn option(&self) -> &IndexOption {
+        &self.create.option
+    }
+
+    pub fn schema(&self) -> &[SchemaField] {
+        &self.create.schema
+    }
+
+    pub fn ft_create(&self) -> &FTCreate {
+        &self.create
+    }
+
+    pub async fn create<C: aio::ConnectionLike + Send>(&self, conn: &mut C) -> crate::Result<()> {
+        AsyncSearchCommands::ft_create(conn, &self.create).await
+    }
+
+    pub async fn drop<C: aio::ConnectionLike + Send>(
+        &self,
+        conn: &mut C,
+        delete_documents: bool,
+    ) -> crate::Result<()> {
+        AsyncSearchCommands::ft_drop(conn, self.name(), delete_documents).await
+    }
+
+    pub async fn info<C: aio::ConnectionLike + Send>(
+        &self,
+        conn: &mut C,
+    ) -> crate::Result<IndexInfo> {
+        AsyncSearchCommands::ft_info(conn, self.name()).await
+    }
+
+    /// Creates the index unless it exists, returning whether it was created.
+    pub async fn ensure_exists<C: aio::ConnectionLike + Send>(
+        &self,
+        conn: &mut C,
+    ) -> crate::Result<bool> {
+        match self.info(conn).await {
+            Ok(_) => return Ok(false),
+            Err(crate::Error::UnknownIndex) => {}
+            Err(err) => return Err(err),
+        }
+        match self.create(conn).await {
+            Ok(()) => Ok(true),
+            Err(crate::Error::IndexAlreadyExists) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn search<C: aio::ConnectionLike + Send>(
+        &self,
+        conn: &mut C,
+        expr: Expr,
+    ) -> crate::Result<SearchResults<T>>
+    where
+        T: DecodeDocument,
+    {
+        let search = T::search(self.name(), expr).build()?;
+        AsyncSearchCommands::ft_search(conn, &search).await
+    }
+
+    /// Runs the aggregation `pipeline` adds to an FT.AGGREGATE of `expr`.
+    pub async fn aggregate<C, R, F>(
+        &self,
+        conn: &mut C,
+        expr: Expr,
+        pipeline: F,
+    ) -> crate::Result<AggregateResults<R>>
+    where
+        C: aio::ConnectionLike + Send,
+        R: FromRedisValue,
+        F: FnOnce(FTAggregateBuilder) -> FTAggregateBuilder,
+    {
+        let aggregate = pipeline(T::aggregate(self.name(), expr)).build()?;
+        AsyncSearchCommands::ft_aggregate(conn, &aggregate).await
+    }
+
+    pub fn create_blocking<C: ConnectionLike>(&self, conn: &mut C) -> crate::Result<()> {
+        SearchCommands::ft_create_blocking(conn, &self.create)
+    }
+
+    pub fn drop_blocking<C: ConnectionLike>(
+        &self,
+        conn: &mut C,
+        delete_documents: bool,
+    ) -> crate::Result<()> {
+        SearchCommands::ft_drop_blocking(conn, self.name(), delete_documents)
+    }
+
+    pub fn info_blocking<C: ConnectionLike>(&self, conn: &mut C) -> crate::Result<IndexInfo> {
+        SearchCommands::ft_info_blocking(conn, self.name())
+    }
+
+    pub fn ensure_exists_blocking<C: ConnectionLike>(&self, conn: &mut C) -> crate::Result<bool> {
+        match self.info_blocking(conn) {
+            Ok(_) => return Ok(false),
+            Err(crate::Error::UnknownIndex) => {}
+            Err(err) => return Err(err),
+        }
+        match self.create_blocking(conn) {
+            Ok(()) => Ok(true),
+            Err(crate::Error::IndexAlreadyExists) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn search_blocking<C: ConnectionLike>(
+        &self,
+        conn: &mut C,
+        expr: Expr,
+    ) -> crate::Result<SearchResults<T>>
+    where
+        T: DecodeDocument,
+    {
+        let search = T::search(self.name(), expr).build()?;
+        SearchCommands::ft_search_blocking(conn, &search)
+    }
+
+    pub fn aggregate_blocking<C, R, F>(
+        &self,
+        conn: &mut C,
+        expr: Expr,
+        pipeline: F,
+    ) -> crate::Result<AggregateResults<R>>
+    where
+        C: ConnectionLike,
+        R: FromRedisValue,
+        F: FnOnce(FTAggregateBuilder) -> FTAggregateBuilder,
+    {
+        let aggregate = pipeline(T::aggregate(self.name(), expr)).build()?;
+        SearchCommands::ft_aggregate_blocking(conn, &aggregate)
+    }
+}
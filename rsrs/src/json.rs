@@ -0,0 +1,89 @@
+use redis::Value;
+use serde::de::DeserializeOwned;
+use serde_json::Map;
+
+use crate::{query::FTSearchReturn, DecodeDocument, DecodeField};
+
+/// Decodes a hit of an `ON JSON` index through serde.
+///
+/// The whole document is read from `$` when it is present, otherwise the
+/// returned fields are deserialized as an object keyed by their names, with any
+/// `$.` prefix stripped. Only JSONPath projections are parsed as JSON, the
+/// values of attributes are kept as strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned> DecodeDocument for Json<T> {
+    fn decode_document(key: &str, fields: Vec<(String, Value)>) -> crate::Result<Self> {
+        Self::decode_returned(key, fields, &[])
+    }
+
+    fn decode_returned(
+        _key: &str,
+        fields: Vec<(String, Value)>,
+        returns: &[FTSearchReturn],
+    ) -> crate::Result<Self> {
+        let mut object = Map::new();
+        for (name, value) in fields {
+            let is_path = name.starts_with('$')
+                || returns.iter().any(|x| {
+                    x.property.as_deref() == Some(name.as_str()) && x.identifier.starts_with('$')
+                });
+            let value = if is_path {
+                parse_json(&name, &value)?
+            } else {
+                serde_json::Value::String(decode_string(&name, &value)?)
+            };
+            if name == "$" {
+                return from_json("$", unwrap_dialect3(value)).map(Json);
+            }
+            let name = name.strip_prefix("$.").unwrap_or(&name).to_string();
+            object.insert(name, value);
+        }
+        from_json("$", object.into()).map(Json)
+    }
+}
+
+fn decode_string(field: &str, value: &Value) -> crate::Result<String> {
+    String::decode_field(value).map_err(|reason| crate::Error::FieldDecode {
+        field: field.to_string(),
+        reason,
+    })
+}
+
+fn parse_json(field: &str, value: &Value) -> crate::Result<serde_json::Value> {
+    let text = decode_string(field, value)?;
+    // Scalars of JSONPath projections are returned without quotes.
+    Ok(serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text)))
+}
+
+/// With DIALECT 3 every JSONPath result is wrapped in an array.
+fn unwrap_dialect3(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Array(mut values) if values.len() == 1 && values[0].is_object() => {
+            values.remove(0)
+        }
+        value => value,
+    }
+}
+
+fn from_json<T: DeserializeOwned>(field: &str, value: serde_json::Value) -> crate::Result<T> {
+    serde_json::from_value(value).map_err(|err| crate::Error::FieldDecode {
+        field: field.to_string(),
+        reason: err.to_string(),
+    })
+}
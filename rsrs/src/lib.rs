@@ -1,13 +1,30 @@
+#![allow(clippy::upper_case_acronyms)]
+
 pub mod field;
 pub use field::*;
 pub mod document;
 pub use document::*;
+pub mod expr;
+pub use expr::*;
 pub mod create;
 pub use create::*;
 pub mod error;
 pub use error::*;
+pub mod aggregate;
 pub mod query;
+pub mod response;
+pub use response::*;
+pub mod encode;
+pub use encode::*;
+pub mod client;
+pub use client::*;
+pub mod index;
+pub use index::*;
+#[cfg(feature = "serde")]
+pub mod json;
 
 extern crate rsrs_derive;
 
+pub use redis;
+
 pub use rsrs_derive::Document;
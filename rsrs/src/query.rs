@@ -1,12 +1,30 @@
 use bytes::Bytes;
 use redis::ToRedisArgs;
 
+use crate::{DecodeDocument, Expr, SearchResults};
+
+#[derive(Debug)]
 pub struct FTSearch {
     pub query: String,
     pub index: String,
     pub option: FTSearchOption,
 }
 
+impl FTSearch {
+    pub fn to_cmd(&self) -> redis::Cmd {
+        let mut cmd = redis::cmd("FT.SEARCH");
+        cmd.arg(self);
+        cmd
+    }
+
+    pub fn parse_reply<T: DecodeDocument>(
+        &self,
+        reply: redis::Value,
+    ) -> crate::Result<SearchResults<T>> {
+        SearchResults::from_reply(reply, &self.option)
+    }
+}
+
 impl ToRedisArgs for FTSearch {
     fn write_redis_args<W>(&self, out: &mut W)
     where
@@ -18,25 +36,285 @@ impl ToRedisArgs for FTSearch {
     }
 }
 
+#[derive(Debug)]
+pub struct FTSearchBuilder {
+    index: String,
+    expr: Expr,
+}
+
+impl FTSearchBuilder {
+    pub fn new(index: &str, expr: Expr) -> FTSearchBuilder {
+        FTSearchBuilder {
+            index: index.to_string(),
+            expr,
+        }
+    }
+
+    pub fn build(&self) -> crate::Result<FTSearch> {
+        self.expr.ft_search(&self.index)
+    }
+
+    fn search_option_mut(&mut self) -> &mut FTSearchOption {
+        &mut self.expr.option
+    }
+}
+
+impl_search_option!(FTSearchBuilder);
+
+/// Generates the FT.SEARCH option setters for a type exposing
+/// `fn search_option_mut(&mut self) -> &mut FTSearchOption`.
+macro_rules! impl_search_option {
+    ($ty:ty) => {
+        impl $ty {
+            pub fn limit(mut self, offset: usize, num: usize) -> Self {
+                self.search_option_mut().limit = Some($crate::query::Limit { offset, num });
+                self
+            }
+
+            pub fn no_content(mut self) -> Self {
+                self.search_option_mut().no_content = true;
+                self
+            }
+
+            pub fn verbatim(mut self) -> Self {
+                self.search_option_mut().verbatim = true;
+                self
+            }
+
+            pub fn no_stopwords(mut self) -> Self {
+                self.search_option_mut().no_stopwords = true;
+                self
+            }
+
+            pub fn with_scores(mut self) -> Self {
+                self.search_option_mut().with_scores = true;
+                self
+            }
+
+            pub fn with_payloads(mut self) -> Self {
+                self.search_option_mut().with_payloads = true;
+                self
+            }
+
+            pub fn with_sort_keys(mut self) -> Self {
+                self.search_option_mut().with_sort_keys = true;
+                self
+            }
+
+            pub fn in_keys<T: AsRef<str>>(mut self, keys: &[T]) -> Self {
+                self.search_option_mut().in_keys =
+                    Some(keys.iter().map(|x| x.as_ref().to_string()).collect());
+                self
+            }
+
+            pub fn in_fields(mut self, fields: &[&dyn $crate::Field]) -> Self {
+                self.search_option_mut().in_fields =
+                    Some(fields.iter().map(|x| x.field_name().to_string()).collect());
+                self
+            }
+
+            pub fn return_fields(mut self, fields: &[&dyn $crate::Field]) -> Self {
+                self.search_option_mut()
+                    .returns
+                    .get_or_insert_with(Vec::new)
+                    .extend(fields.iter().map(|x| $crate::query::FTSearchReturn {
+                        identifier: x.field_name().to_string(),
+                        property: None,
+                    }));
+                self
+            }
+
+            /// Returns `identifier`, e.g. a JSONPath, under the name `property`.
+            pub fn return_as(mut self, identifier: &str, property: &str) -> Self {
+                self.search_option_mut()
+                    .returns
+                    .get_or_insert_with(Vec::new)
+                    .push($crate::query::FTSearchReturn {
+                        identifier: identifier.to_string(),
+                        property: Some(property.to_string()),
+                    });
+                self
+            }
+
+            pub fn slop(mut self, slop: usize) -> Self {
+                self.search_option_mut().slop = Some(slop);
+                self
+            }
+
+            pub fn timeout(mut self, timeout: usize) -> Self {
+                self.search_option_mut().timeout = Some(timeout);
+                self
+            }
+
+            pub fn in_order(mut self) -> Self {
+                self.search_option_mut().in_order = true;
+                self
+            }
+
+            pub fn language(mut self, language: $crate::Language) -> Self {
+                self.search_option_mut().language = Some(language);
+                self
+            }
+
+            pub fn expander(mut self, expander: &str) -> Self {
+                self.search_option_mut().expander = Some(expander.to_string());
+                self
+            }
+
+            pub fn scorer(mut self, scorer: &str) -> Self {
+                self.search_option_mut().scorer = Some(scorer.to_string());
+                self
+            }
+
+            /// EXPLAINSCORE only works together with WITHSCORES, so both are set.
+            pub fn explain_score(mut self) -> Self {
+                let option = self.search_option_mut();
+                option.with_scores = true;
+                option.explain_score = true;
+                self
+            }
+
+            pub fn sort_by<F: $crate::Field>(mut self, field: F, order: $crate::SortOrder) -> Self {
+                self.search_option_mut().sortby = Some($crate::query::SortBy {
+                    attribute: field.field_name(),
+                    asc: order == $crate::SortOrder::ASC,
+                    with_count: false,
+                });
+                self
+            }
+
+            pub fn dialect(mut self, dialect: usize) -> Self {
+                self.search_option_mut().dialect = Some(dialect);
+                self
+            }
+        }
+    };
+}
+
+pub(crate) use impl_search_option;
+
+#[derive(Default, Debug, Clone)]
 pub struct FTSearchOption {
+    pub no_content: bool,
+    pub verbatim: bool,
+    pub no_stopwords: bool,
+    pub with_scores: bool,
+    pub with_payloads: bool,
+    pub with_sort_keys: bool,
+    pub in_keys: Option<Vec<String>>,
+    pub in_fields: Option<Vec<String>>,
     pub returns: Option<Vec<FTSearchReturn>>,
+    pub slop: Option<usize>,
+    pub timeout: Option<usize>,
+    pub in_order: bool,
+    pub language: Option<crate::Language>,
+    pub expander: Option<String>,
+    pub scorer: Option<String>,
+    pub explain_score: bool,
     pub params: Option<Vec<FTSearchParam>>,
     pub sortby: Option<SortBy>,
+    pub limit: Option<Limit>,
     pub dialect: Option<usize>,
 }
 
+impl FTSearchOption {
+    pub(crate) fn merge(self, other: FTSearchOption) -> FTSearchOption {
+        FTSearchOption {
+            no_content: self.no_content || other.no_content,
+            verbatim: self.verbatim || other.verbatim,
+            no_stopwords: self.no_stopwords || other.no_stopwords,
+            with_scores: self.with_scores || other.with_scores,
+            with_payloads: self.with_payloads || other.with_payloads,
+            with_sort_keys: self.with_sort_keys || other.with_sort_keys,
+            in_keys: self.in_keys.or(other.in_keys),
+            in_fields: self.in_fields.or(other.in_fields),
+            returns: self.returns.or(other.returns),
+            slop: self.slop.or(other.slop),
+            timeout: self.timeout.or(other.timeout),
+            in_order: self.in_order || other.in_order,
+            language: self.language.or(other.language),
+            expander: self.expander.or(other.expander),
+            scorer: self.scorer.or(other.scorer),
+            explain_score: self.explain_score || other.explain_score,
+            params: self.params.or(other.params),
+            sortby: self.sortby.or(other.sortby),
+            limit: self.limit.or(other.limit),
+            // Both sides must parse, so the higher dialect wins.
+            dialect: self.dialect.max(other.dialect),
+        }
+    }
+}
+
 impl ToRedisArgs for FTSearchOption {
     fn write_redis_args<W>(&self, out: &mut W)
     where
         W: ?Sized + redis::RedisWrite,
     {
+        if self.no_content {
+            "NOCONTENT".write_redis_args(out);
+        }
+        if self.verbatim {
+            "VERBATIM".write_redis_args(out);
+        }
+        if self.no_stopwords {
+            "NOSTOPWORDS".write_redis_args(out);
+        }
+        if self.with_scores {
+            "WITHSCORES".write_redis_args(out);
+        }
+        if self.with_payloads {
+            "WITHPAYLOADS".write_redis_args(out);
+        }
+        if self.with_sort_keys {
+            "WITHSORTKEYS".write_redis_args(out);
+        }
+        if let Some(in_keys) = &self.in_keys {
+            "INKEYS".write_redis_args(out);
+            in_keys.len().write_redis_args(out);
+            in_keys.write_redis_args(out);
+        }
+        if let Some(in_fields) = &self.in_fields {
+            "INFIELDS".write_redis_args(out);
+            in_fields.len().write_redis_args(out);
+            in_fields.write_redis_args(out);
+        }
         if let Some(returns) = &self.returns {
             "RETURN".write_redis_args(out);
-            returns.len().write_redis_args(out);
+            returns
+                .iter()
+                .map(|x| if x.property.is_some() { 3 } else { 1 })
+                .sum::<usize>()
+                .write_redis_args(out);
             returns.write_redis_args(out);
         }
+        if let Some(slop) = self.slop {
+            "SLOP".write_redis_args(out);
+            slop.write_redis_args(out);
+        }
+        if let Some(timeout) = self.timeout {
+            "TIMEOUT".write_redis_args(out);
+            timeout.write_redis_args(out);
+        }
+        if self.in_order {
+            "INORDER".write_redis_args(out);
+        }
+        if let Some(language) = &self.language {
+            "LANGUAGE".write_redis_args(out);
+            language.write_redis_args(out);
+        }
+        if let Some(expander) = &self.expander {
+            "EXPANDER".write_redis_args(out);
+            expander.write_redis_args(out);
+        }
+        if let Some(scorer) = &self.scorer {
+            "SCORER".write_redis_args(out);
+            scorer.write_redis_args(out);
+        }
+        if self.explain_score {
+            "EXPLAINSCORE".write_redis_args(out);
+        }
         match &self.params {
-            Some(params) if params.len() > 0 => {
+            Some(params) if !params.is_empty() => {
                 "PARAMS".write_redis_args(out);
                 (params.len() * 2).write_redis_args(out);
                 params.write_redis_args(out);
@@ -47,6 +325,10 @@ impl ToRedisArgs for FTSearchOption {
             "SORTBY".write_redis_args(out);
             sort_by.write_redis_args(out);
         }
+        if let Some(limit) = &self.limit {
+            "LIMIT".write_redis_args(out);
+            limit.write_redis_args(out);
+        }
         if let Some(dialect) = self.dialect {
             "DIALECT".write_redis_args(out);
             dialect.write_redis_args(out);
@@ -54,10 +336,11 @@ impl ToRedisArgs for FTSearchOption {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct SortBy {
     pub attribute: &'static str,
     pub asc: bool,
-    pub with_count: Option<usize>,
+    pub with_count: bool,
 }
 
 impl ToRedisArgs for SortBy {
@@ -67,15 +350,32 @@ impl ToRedisArgs for SortBy {
     {
         self.attribute.write_redis_args(out);
         (if self.asc { "ASC" } else { "DESC" }).write_redis_args(out);
-        if let Some(with_count) = self.with_count {
-            with_count.write_redis_args(out);
+        if self.with_count {
+            "WITHCOUNT".write_redis_args(out);
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct Limit {
+    pub offset: usize,
+    pub num: usize,
+}
+
+impl ToRedisArgs for Limit {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + redis::RedisWrite,
+    {
+        self.offset.write_redis_args(out);
+        self.num.write_redis_args(out);
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct FTSearchReturn {
-    pub identifier: &'static str,
-    pub property: &'static str,
+    pub identifier: String,
+    pub property: Option<String>,
 }
 
 impl ToRedisArgs for FTSearchReturn {
@@ -84,11 +384,14 @@ impl ToRedisArgs for FTSearchReturn {
         W: ?Sized + redis::RedisWrite,
     {
         self.identifier.write_redis_args(out);
-        // "AS".write_redis_args(out);
-        // self.property.write_redis_args(out);
+        if let Some(property) = &self.property {
+            "AS".write_redis_args(out);
+            property.write_redis_args(out);
+        }
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct FTSearchParam {
     pub name: String,
     pub value: Bytes,
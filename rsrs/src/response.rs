@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use redis::{FromRedisValue, Value};
+
+use crate::query::FTSearchOption;
+
+/// Decodes the field array of a single FT.SEARCH hit.
+pub trait DecodeDocument: Sized {
+    fn decode_document(key: &str, fields: Vec<(String, Value)>) -> crate::Result<Self>;
+
+    /// Like [`DecodeDocument::decode_document`], knowing the RETURN of the
+    /// search, which tells aliased JSONPath projections apart from attributes.
+    fn decode_returned(
+        key: &str,
+        fields: Vec<(String, Value)>,
+        _returns: &[crate::query::FTSearchReturn],
+    ) -> crate::Result<Self> {
+        Self::decode_document(key, fields)
+    }
+}
+
+impl<V: FromRedisValue> DecodeDocument for HashMap<String, V> {
+    fn decode_document(_key: &str, fields: Vec<(String, Value)>) -> crate::Result<Self> {
+        fields
+            .into_iter()
+            .map(|(k, v)| Ok((k, V::from_redis_value(&v)?)))
+            .collect()
+    }
+}
+
+impl DecodeDocument for Vec<(String, Value)> {
+    fn decode_document(_key: &str, fields: Vec<(String, Value)>) -> crate::Result<Self> {
+        Ok(fields)
+    }
+}
+
+/// Parses the `#[id]` field of a document out of its key, used by
+/// `#[derive(Document)]`.
+pub fn decode_id<T: DecodeField>(key: &str, prefix: &str, field: &'static str) -> crate::Result<T> {
+    let ret = match key.strip_prefix(prefix) {
+        Some(id) => T::decode_field(&Value::Data(id.as_bytes().to_vec())),
+        None => Err(format!("key '{}' does not start with '{}'", key, prefix)),
+    };
+    ret.map_err(|reason| crate::Error::FieldDecode {
+        field: field.to_string(),
+        reason,
+    })
+}
+
+/// Decodes a single field value of a document.
+pub trait DecodeField: Sized {
+    fn decode_field(value: &Value) -> Result<Self, String>;
+
+    /// Value used when the field is absent from the reply.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+macro_rules! impl_decode_field_number {
+    ($($ty:ty),*) => {
+        $(
+            impl DecodeField for $ty {
+                fn decode_field(value: &Value) -> Result<Self, String> {
+                    let s = String::decode_field(value)?;
+                    s.parse()
+                        .map_err(|err| format!("cannot parse '{}' as {}: {}", s, stringify!($ty), err))
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_field_number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+impl DecodeField for String {
+    fn decode_field(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Data(data) => String::from_utf8(data.clone()).map_err(|err| err.to_string()),
+            Value::Status(s) => Ok(s.clone()),
+            Value::Int(i) => Ok(i.to_string()),
+            _ => Err(format!("unexpected value {:?}", value)),
+        }
+    }
+}
+
+macro_rules! impl_decode_field_vector {
+    ($($ty:ty),*) => {
+        $(
+            impl DecodeField for Vec<$ty> {
+                fn decode_field(value: &Value) -> Result<Self, String> {
+                    let Value::Data(data) = value else {
+                        return Err(format!("unexpected value {:?}", value));
+                    };
+                    if data.len() % std::mem::size_of::<$ty>() != 0 {
+                        return Err(format!(
+                            "blob of {} bytes is not a vector of {}",
+                            data.len(),
+                            stringify!($ty)
+                        ));
+                    }
+                    Ok(data
+                        .chunks_exact(std::mem::size_of::<$ty>())
+                        .map(|x| <$ty>::from_le_bytes(x.try_into().unwrap()))
+                        .collect())
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_field_vector!(f32, f64);
+
+impl<T: DecodeField> DecodeField for Option<T> {
+    fn decode_field(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::decode_field(value).map(Some),
+        }
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+/// Decodes `field` out of the fields of a hit, used by `#[derive(Document)]`.
+pub fn decode_field<T: DecodeField>(
+    fields: &mut HashMap<String, Value>,
+    field: &'static str,
+) -> crate::Result<T> {
+    let ret = match fields.remove(field) {
+        Some(value) => T::decode_field(&value),
+        None => T::missing().ok_or_else(|| "missing from reply".to_string()),
+    };
+    ret.map_err(|reason| crate::Error::FieldDecode {
+        field: field.to_string(),
+        reason,
+    })
+}
+
+/// The reply of FT.INFO.
+#[derive(Debug, Clone, Default)]
+pub struct IndexInfo {
+    pub index_name: String,
+    pub num_docs: usize,
+    pub num_records: usize,
+    pub indexing: bool,
+    pub percent_indexed: f64,
+    pub hash_indexing_failures: usize,
+    /// Every entry of the reply, including the ones above.
+    pub raw: Vec<(String, Value)>,
+}
+
+impl IndexInfo {
+    pub fn from_reply(reply: Value) -> crate::Result<Self> {
+        let raw = decode_fields(reply)?;
+        let mut info = IndexInfo::default();
+        for (name, value) in &raw {
+            let decode = |field: &str| -> crate::Result<String> {
+                String::decode_field(value).map_err(|reason| crate::Error::FieldDecode {
+                    field: field.to_string(),
+                    reason,
+                })
+            };
+            let parse = |field: &str| -> crate::Result<f64> {
+                let value = decode(field)?;
+                value.parse().map_err(|_| crate::Error::FieldDecode {
+                    field: field.to_string(),
+                    reason: format!("cannot parse '{}' as a number", value),
+                })
+            };
+            match name.as_str() {
+                "index_name" => info.index_name = decode(name)?,
+                "num_docs" => info.num_docs = parse(name)? as usize,
+                "num_records" => info.num_records = parse(name)? as usize,
+                "indexing" => info.indexing = parse(name)? != 0.,
+                "percent_indexed" => info.percent_indexed = parse(name)?,
+                "hash_indexing_failures" => info.hash_indexing_failures = parse(name)? as usize,
+                _ => {}
+            }
+        }
+        info.raw = raw;
+        Ok(info)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResults<T> {
+    pub total: usize,
+    pub hits: Vec<SearchHit<T>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit<T> {
+    pub key: String,
+    pub score: Option<f64>,
+    pub payload: Option<Vec<u8>>,
+    pub sort_key: Option<String>,
+    /// `None` when the search was run with NOCONTENT.
+    pub document: Option<T>,
+}
+
+impl<T: DecodeDocument> SearchResults<T> {
+    /// Decodes an FT.SEARCH reply. The `option` the search was run with decides
+    /// which entries each hit is made of.
+    pub fn from_reply(reply: Value, option: &FTSearchOption) -> crate::Result<Self> {
+        let Value::Bulk(reply) = reply else {
+            return Err(crate::Error::UnexpectedReply);
+        };
+        let mut reply = reply.into_iter();
+        let total = match reply.next() {
+            Some(total) => usize::from_redis_value(&total)?,
+            None => return Err(crate::Error::UnexpectedReply),
+        };
+
+        let mut hits = vec![];
+        while let Some(key) = reply.next() {
+            let key = String::from_redis_value(&key)?;
+            let score = if option.with_scores {
+                Some(decode_score(next(&mut reply)?)?)
+            } else {
+                None
+            };
+            let payload = if option.with_payloads {
+                Option::<Vec<u8>>::from_redis_value(&next(&mut reply)?)?
+            } else {
+                None
+            };
+            let sort_key = if option.with_sort_keys {
+                Option::<String>::from_redis_value(&next(&mut reply)?)?
+            } else {
+                None
+            };
+            let document = if option.no_content {
+                None
+            } else {
+                Some(T::decode_returned(
+                    &key,
+                    decode_fields(next(&mut reply)?)?,
+                    option.returns.as_deref().unwrap_or_default(),
+                )?)
+            };
+            hits.push(SearchHit {
+                key,
+                score,
+                payload,
+                sort_key,
+                document,
+            });
+        }
+
+        Ok(SearchResults { total, hits })
+    }
+}
+
+fn next(reply: &mut impl Iterator<Item = Value>) -> crate::Result<Value> {
+    reply.next().ok_or(crate::Error::UnexpectedReply)
+}
+
+/// With EXPLAINSCORE the score comes as `[score, explanation]`.
+fn decode_score(score: Value) -> crate::Result<f64> {
+    match score {
+        Value::Bulk(mut score) if !score.is_empty() => {
+            Ok(f64::from_redis_value(&score.swap_remove(0))?)
+        }
+        score => Ok(f64::from_redis_value(&score)?),
+    }
+}
+
+pub(crate) fn decode_fields(fields: Value) -> crate::Result<Vec<(String, Value)>> {
+    let fields = match fields {
+        Value::Bulk(fields) => fields,
+        Value::Nil => vec![],
+        _ => return Err(crate::Error::UnexpectedReply),
+    };
+    if fields.len() % 2 != 0 {
+        return Err(crate::Error::UnexpectedReply);
+    }
+    let mut fields = fields.into_iter();
+    let mut ret = vec![];
+    while let (Some(k), Some(v)) = (fields.next(), fields.next()) {
+        ret.push((String::from_redis_value(&k)?, v));
+    }
+    Ok(ret)
+}
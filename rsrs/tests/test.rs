@@ -1,8 +1,34 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use futures_util::StreamExt;
 use itertools::Itertools;
 use redis::ToRedisArgs;
+use rsrs::aggregate::apply::*;
+use rsrs::aggregate::AggregateCursor;
+use rsrs::aggregate::AggregateResults;
+use rsrs::aggregate::ApplyExpr;
+use rsrs::aggregate::Reducer;
+#[cfg(feature = "serde")]
+use rsrs::json::Json;
 use rsrs::DataType;
 use rsrs::Document;
+use rsrs::DocumentWrite;
+use rsrs::Expr;
+use rsrs::FieldKind;
+use rsrs::GeoUnit;
+use rsrs::Index;
 use rsrs::IndexOption;
+use rsrs::Language;
+use rsrs::NumberField;
+use rsrs::NumericOptions;
+use rsrs::QueryAttributes;
+use rsrs::QueryNode;
+use rsrs::SchemaField;
+use rsrs::SearchResults;
+use rsrs::SortOrder;
+use rsrs::Sortable;
+use rsrs::VectorField;
 
 #[derive(Document)]
 struct Demo {
@@ -66,7 +92,7 @@ fn test_create() {
             ..Default::default()
         },
     );
-    assert_eq!(to_redis_string(&ftcreate).as_str(), "my_index ON JSON SCHEMA $.description AS description TEXT SORTABLE NOINDEX $.vector32 AS vector32 VECTOR FLAT 6 TYPE FLOAT32 DIM 768 DISTANCE_METRIC L2 $.vector64 AS vector64 VECTOR FLAT 6 TYPE FLOAT32 DIM 768 DISTANCE_METRIC L2 $.a1 AS a1 NUMERIC $.a2 AS a2 NUMERIC $.a3 AS a3 NUMERIC $.a4 AS a4 NUMERIC $.a5 AS a5 NUMERIC $.a6 AS a6 NUMERIC $.a7 AS a7 NUMERIC $.a8 AS a8 NUMERIC $.a9 AS a9 NUMERIC $.a10 AS a10 NUMERIC $.a11 AS a11 NUMERIC NOINDEX $.a12 AS a12 NUMERIC SORTABLE $.a13 AS a13 NUMERIC $.a14 AS a14 NUMERIC");
+    assert_eq!(to_redis_string(&ftcreate).as_str(), "my_index ON JSON SCHEMA $.description AS description TEXT SORTABLE NOINDEX $.vector32 AS vector32 VECTOR FLAT 6 TYPE FLOAT32 DIM 768 DISTANCE_METRIC L2 $.vector64 AS vector64 VECTOR FLAT 6 TYPE FLOAT64 DIM 768 DISTANCE_METRIC L2 $.a1 AS a1 NUMERIC $.a2 AS a2 NUMERIC $.a3 AS a3 NUMERIC $.a4 AS a4 NUMERIC $.a5 AS a5 NUMERIC $.a6 AS a6 NUMERIC $.a7 AS a7 NUMERIC $.a8 AS a8 NUMERIC $.a9 AS a9 NUMERIC $.a10 AS a10 NUMERIC $.a11 AS a11 NUMERIC NOINDEX $.a12 AS a12 NUMERIC SORTABLE $.a13 AS a13 NUMERIC $.a14 AS a14 NUMERIC");
 }
 
 #[test]
@@ -150,7 +176,7 @@ fn test_vector_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index *=>[KNN 3 @vector32 $vec] PARAMS 2 $vec <4Bytes>"
+        "my_index *=>[KNN 3 @vector32 $vec] PARAMS 2 vec <4Bytes>"
     );
 
     assert_eq!(
@@ -161,7 +187,7 @@ fn test_vector_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index *=>[KNN 3 @vector32 $blob] PARAMS 2 $blob <4Bytes>"
+        "my_index *=>[KNN 3 @vector32 $blob] PARAMS 2 blob <4Bytes>"
     );
 
     assert_eq!(
@@ -172,7 +198,7 @@ fn test_vector_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index *=>[KNN 3 @vector32 $vec] PARAMS 2 $vec <8Bytes>"
+        "my_index *=>[KNN 3 @vector32 $vec] PARAMS 2 vec <8Bytes>"
     );
 
     assert_eq!(
@@ -184,7 +210,7 @@ fn test_vector_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index *=>[KNN 3 @vector32 $ok] PARAMS 2 $ok <8Bytes> DIALECT 2"
+        "my_index *=>[KNN 3 @vector32 $ok] PARAMS 2 ok <8Bytes> DIALECT 2"
     );
 
     assert_eq!(
@@ -196,7 +222,7 @@ fn test_vector_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index *=>[KNN 3 @vector64 $vec] PARAMS 2 $vec <4Bytes> DIALECT 2"
+        "my_index *=>[KNN 3 @vector64 $vec] PARAMS 2 vec <8Bytes> DIALECT 2"
     );
 
     assert_eq!(
@@ -207,17 +233,15 @@ fn test_vector_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index *=>[KNN 3 @vector64 $vec] PARAMS 2 $vec <8Bytes>"
+        "my_index *=>[KNN 3 @vector64 $vec] PARAMS 2 vec <16Bytes>"
     );
 
-    assert_eq!(
-        op.vector32
-            .query(&[1.], 3, None)
-            .and(op.vector32.query(&[2.], 3, None))
-            .ft_search("my_index")
-            .is_err(),
-        true
-    );
+    assert!(op
+        .vector32
+        .query(&[1.], 3, None)
+        .and(op.vector32.query(&[2.], 3, None))
+        .ft_search("my_index")
+        .is_err());
 }
 
 #[test]
@@ -225,7 +249,7 @@ fn test_text_query() {
     let op = Demo::op();
     assert_eq!(
         to_redis_string(&op.description.eq("aaa").ft_search("my_index").unwrap()).as_str(),
-        "my_index @description:{ aaa }"
+        "my_index @description:aaa"
     );
     assert_eq!(
         to_redis_string(
@@ -235,7 +259,7 @@ fn test_text_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index @description:{ aaa | bbb | ccc }"
+        "my_index @description:(aaa|bbb|ccc)"
     );
     assert_eq!(
         to_redis_string(
@@ -245,8 +269,53 @@ fn test_text_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index -@description:{ aaa | bbb | ccc }"
+        "my_index -@description:(aaa|bbb|ccc)"
+    );
+
+    let search = |expr: Expr| to_redis_string(&expr.ft_search("my_index").unwrap());
+    assert_eq!(
+        search(op.description.phrase("hello world")),
+        "my_index @description:\"hello world\""
+    );
+    assert_eq!(
+        search(op.description.prefix("hel")),
+        "my_index @description:hel*"
+    );
+    assert_eq!(
+        search(op.description.suffix("llo")),
+        "my_index @description:*llo"
+    );
+    assert_eq!(
+        search(op.description.infix("ell")),
+        "my_index @description:*ell*"
+    );
+    assert_eq!(
+        search(op.description.fuzzy("helo", 2)),
+        "my_index @description:%%helo%%"
+    );
+    assert_eq!(
+        search(
+            op.description
+                .eq("hello")
+                .and(op.description.optional("world"))
+        ),
+        "my_index @description:hello ~@description:world"
+    );
+    assert_eq!(
+        search(op.description.wildcard("he?lo*")),
+        "my_index @description:w'he?lo*' DIALECT 2"
     );
+    for expr in [
+        op.description.fuzzy("helo", 4),
+        op.description.eq(""),
+        op.description.contains::<&str>(&[]),
+        op.description.not_contains(&["a", ""]),
+    ] {
+        assert!(matches!(
+            expr.ft_search("my_index"),
+            Err(rsrs::Error::InvalidQuery(_))
+        ));
+    }
 }
 
 #[test]
@@ -262,7 +331,7 @@ fn test_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index @description:{ aaa } @description:{ bbb }"
+        "my_index @description:aaa @description:bbb"
     );
 
     assert_eq!(
@@ -274,7 +343,7 @@ fn test_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index (@description:{ aaa })=>[KNN 3 @vector32 $vec] PARAMS 2 $vec <4Bytes>"
+        "my_index (@description:aaa)=>[KNN 3 @vector32 $vec] PARAMS 2 vec <4Bytes>"
     );
 
     assert_eq!(
@@ -286,7 +355,7 @@ fn test_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index @description:{ aaa } (@description:{ bbb })|(@description:{ ccc })"
+        "my_index @description:aaa (@description:bbb | @description:ccc)"
     );
 
     assert_eq!(
@@ -298,6 +367,1977 @@ fn test_query() {
                 .unwrap()
         )
         .as_str(),
-        "my_index (@a1:[-inf (10])|(@a1:[20 +inf])"
+        "my_index @a1:[-inf (10] | @a1:[20 +inf]"
+    );
+}
+
+#[test]
+fn test_query_tree() {
+    let op = Demo::op();
+
+    assert_eq!(
+        to_redis_string(
+            &op.a1
+                .eq(1)
+                .and(op.a2.eq(2))
+                .or(op.a3.eq(3).and(op.a4.eq(4)))
+                .ft_search("my_index")
+                .unwrap()
+        )
+        .as_str(),
+        "my_index (@a1:[1 1] @a2:[2 2]) | (@a3:[3 3] @a4:[4 4])"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &op.a1
+                .eq(1)
+                .or(op.a2.eq(2))
+                .and(op.a3.eq(3).or(op.a4.eq(4)))
+                .ft_search("my_index")
+                .unwrap()
+        )
+        .as_str(),
+        "my_index (@a1:[1 1] | @a2:[2 2]) (@a3:[3 3] | @a4:[4 4])"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &(!op.a1.eq(1).or(op.a2.eq(2)))
+                .ft_search("my_index")
+                .unwrap()
+        )
+        .as_str(),
+        "my_index -(@a1:[1 1] | @a2:[2 2])"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &op.vector32
+                .query(&[1.], 3, None)
+                .and(op.a1.eq(1).or(op.a2.eq(2)))
+                .ft_search("my_index")
+                .unwrap()
+        )
+        .as_str(),
+        "my_index (@a1:[1 1] | @a2:[2 2])=>[KNN 3 @vector32 $vec] PARAMS 2 vec <4Bytes>"
+    );
+
+    let mut expr = op.a1.eq(1).and(op.a2.eq(2).or(op.a3.eq(3)));
+    let mut fields = vec![];
+    expr.root().unwrap().walk(&mut |node| {
+        if let QueryNode::Numeric(numeric) = node {
+            fields.push(numeric.field);
+        }
+    });
+    assert_eq!(fields, vec!["a1", "a2", "a3"]);
+
+    expr.root_mut().unwrap().walk_mut(&mut |node| {
+        if let QueryNode::Numeric(numeric) = node {
+            numeric.field = "a5";
+        }
+    });
+    assert_eq!(
+        to_redis_string(&expr.ft_search("my_index").unwrap()).as_str(),
+        "my_index @a5:[1 1] (@a5:[2 2] | @a5:[3 3])"
+    );
+
+    assert!(matches!(
+        Expr::default().ft_search("my_index"),
+        Err(rsrs::Error::EmptyQueryBody)
+    ));
+
+    let search = |expr: Expr| to_redis_string(&expr.ft_search("my_index").unwrap());
+    assert_eq!(
+        search(op.a14.in_range(f64::NEG_INFINITY..f64::INFINITY)),
+        "my_index @a14:[-inf (+inf]"
+    );
+    assert_eq!(
+        search(op.a13.eq(f32::INFINITY)),
+        "my_index @a13:[+inf +inf]"
+    );
+    for expr in [
+        op.a14.in_range(f64::NAN..),
+        op.a14.eq(f64::NAN),
+        op.a13.in_range(..=f32::NAN),
+    ] {
+        assert!(matches!(
+            expr.ft_search("my_index"),
+            Err(rsrs::Error::InvalidQuery(_))
+        ));
+    }
+
+    let knn = || op.vector32.query(&[1.], 3, None);
+    for expr in [
+        !knn(),
+        knn().optional(),
+        !knn().and(op.a1.eq(1)),
+        knn().and(op.a1.eq(1)).optional(),
+        op.a1.eq(1).or(knn().and(op.a2.eq(2))),
+        knn().or(op.a1.eq(1)),
+    ] {
+        assert!(matches!(
+            expr.ft_search("my_index"),
+            Err(rsrs::Error::InvalidQuery(_))
+        ));
+    }
+}
+
+#[test]
+fn test_search_option() {
+    let op = Demo::op();
+
+    assert_eq!(
+        to_redis_string(
+            &op.a1
+                .eq(1)
+                .sort_by(&op.a12, SortOrder::DESC)
+                .limit(10, 20)
+                .ft_search("my_index")
+                .unwrap()
+        )
+        .as_str(),
+        "my_index @a1:[1 1] SORTBY a12 DESC LIMIT 10 20"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &op.a1
+                .eq(1)
+                .no_content()
+                .verbatim()
+                .no_stopwords()
+                .with_payloads()
+                .with_sort_keys()
+                .explain_score()
+                .in_keys(&["doc:1", "doc:2"])
+                .in_fields(&[&op.description, &op.a1])
+                .slop(1)
+                .timeout(100)
+                .in_order()
+                .language(Language::English)
+                .expander("SYNONYM")
+                .scorer("BM25")
+                .ft_search("my_index")
+                .unwrap()
+        )
+        .as_str(),
+        "my_index @a1:[1 1] NOCONTENT VERBATIM NOSTOPWORDS WITHSCORES WITHPAYLOADS WITHSORTKEYS INKEYS 2 doc:1 doc:2 INFIELDS 2 description a1 SLOP 1 TIMEOUT 100 INORDER LANGUAGE english EXPANDER SYNONYM SCORER BM25 EXPLAINSCORE"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &op.a1
+                .eq(1)
+                .limit(0, 5)
+                .and(op.a2.eq(2).sort_by(&op.a12, SortOrder::ASC).dialect(2))
+                .ft_search("my_index")
+                .unwrap()
+        )
+        .as_str(),
+        "my_index @a1:[1 1] @a2:[2 2] SORTBY a12 ASC LIMIT 0 5 DIALECT 2"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &Demo::search("my_index", op.a1.eq(1))
+                .with_scores()
+                .limit(0, 10)
+                .build()
+                .unwrap()
+        )
+        .as_str(),
+        "my_index @a1:[1 1] WITHSCORES LIMIT 0 10"
+    );
+}
+
+#[test]
+fn test_aggregate() {
+    let op = Demo::op();
+
+    assert_eq!(
+        to_redis_string(
+            &Demo::aggregate("my_index", op.a1.in_range(1..))
+                .load(&[&op.a2, &op.a3])
+                .group_by(
+                    &[&op.a1, &op.description],
+                    vec![
+                        Reducer::count().alias("count"),
+                        Reducer::new("SUM", &["@a2"]),
+                    ]
+                )
+                .apply(ApplyExpr::<Num>::property("count") * 2, "double")
+                .filter(ApplyExpr::<Num>::property("count").gt(1))
+                .sort_by(&[(&"count", SortOrder::DESC), (&op.a1, SortOrder::ASC)], Some(10))
+                .limit(0, 5)
+                .build()
+                .unwrap()
+        )
+        .as_str(),
+        "my_index @a1:[1 +inf] LOAD 2 @a2 @a3 GROUPBY 2 @a1 @description REDUCE COUNT 0 AS count REDUCE SUM 1 @a2 APPLY @count * 2 AS double FILTER @count > 1 SORTBY 4 @count DESC @a1 ASC MAX 10 LIMIT 0 5"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &Demo::aggregate("my_index", Expr::all())
+                .load_all()
+                .sort_by(&[(&op.a12, SortOrder::ASC)], None)
+                .build()
+                .unwrap()
+        )
+        .as_str(),
+        "my_index * LOAD * SORTBY 2 @a12 ASC"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &Demo::aggregate("my_index", op.vector32.query(&[1.], 3, None).dialect(2))
+                .build()
+                .unwrap()
+        )
+        .as_str(),
+        "my_index *=>[KNN 3 @vector32 $vec] PARAMS 2 vec <4Bytes> DIALECT 2"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &Demo::aggregate(
+                "my_index",
+                op.a1
+                    .eq(1)
+                    .verbatim()
+                    .timeout(100)
+                    .sort_by(&op.a12, SortOrder::DESC)
+                    .limit(0, 10)
+            )
+            .load(&[&op.a12])
+            .build()
+            .unwrap()
+        )
+        .as_str(),
+        "my_index @a1:[1 1] VERBATIM TIMEOUT 100 LOAD 1 @a12 SORTBY 2 @a12 DESC LIMIT 0 10"
+    );
+
+    assert!(matches!(
+        Demo::aggregate("my_index", Expr::all().with_scores()).build(),
+        Err(rsrs::Error::InvalidQuery(_))
+    ));
+    assert!(matches!(
+        Demo::aggregate("my_index", Expr::all().return_fields(&[&op.a1])).build(),
+        Err(rsrs::Error::InvalidQuery(_))
+    ));
+}
+
+#[test]
+fn test_reducer() {
+    let op = Demo::op();
+
+    assert_eq!(
+        to_redis_string(
+            &Demo::aggregate("my_index", Expr::all())
+                .group_by(
+                    &[&op.description],
+                    vec![
+                        Reducer::count().alias("count"),
+                        Reducer::count_distinct(&op.a1),
+                        Reducer::count_distinctish(&op.a1),
+                        Reducer::sum(&op.a2).alias("sum"),
+                        Reducer::min(&op.a3),
+                        Reducer::max(&op.a4),
+                        Reducer::avg(&op.a13),
+                        Reducer::stddev(&op.a14),
+                        Reducer::quantile(&op.a14, 0.5).alias("median"),
+                        Reducer::to_list(&op.description),
+                        Reducer::first_value(&op.description),
+                        Reducer::first_value_by(&op.description, &op.a12, SortOrder::DESC),
+                        Reducer::random_sample(&op.a1, 3),
+                    ]
+                )
+                .build()
+                .unwrap()
+        )
+        .as_str(),
+        "my_index * GROUPBY 1 @description REDUCE COUNT 0 AS count REDUCE COUNT_DISTINCT 1 @a1 REDUCE COUNT_DISTINCTISH 1 @a1 REDUCE SUM 1 @a2 AS sum REDUCE MIN 1 @a3 REDUCE MAX 1 @a4 REDUCE AVG 1 @a13 REDUCE STDDEV 1 @a14 REDUCE QUANTILE 2 @a14 0.5 AS median REDUCE TOLIST 1 @description REDUCE FIRST_VALUE 1 @description REDUCE FIRST_VALUE 4 @description BY @a12 DESC REDUCE RANDOM_SAMPLE 2 @a1 3"
+    );
+    for quantile in [-0.1, 1.5, f64::NAN] {
+        assert!(matches!(
+            Demo::aggregate("my_index", Expr::all())
+                .group_by(&[], vec![Reducer::quantile(&op.a14, quantile)])
+                .build(),
+            Err(rsrs::Error::InvalidQuery(_))
+        ));
+    }
+}
+
+#[test]
+fn test_apply_expr() {
+    let op = Demo::op();
+
+    assert_eq!((&op.a13 * &op.a14).as_str(), "@a13 * @a14");
+    assert_eq!(
+        ((&op.a1 + &op.a2) * &op.a3 - 1).as_str(),
+        "(@a1 + @a2) * @a3 - 1"
+    );
+    assert_eq!((&op.a1 - (&op.a2 - &op.a3)).as_str(), "@a1 - (@a2 - @a3)");
+    assert_eq!(
+        ApplyExpr::<Num>::from(&op.a1).pow(2).pow(3).as_str(),
+        "(@a1 ^ 2) ^ 3"
+    );
+    assert_eq!((-(&op.a1 / 2)).as_str(), "-(@a1 / 2)");
+    assert_eq!(ApplyExpr::<Num>::from(-2).pow(2).as_str(), "(-2) ^ 2");
+    assert_eq!(
+        (-ApplyExpr::<Num>::from(&op.a1)).pow(-2).as_str(),
+        "(-@a1) ^ -2"
+    );
+    assert!(matches!(
+        Demo::aggregate("my_index", Expr::all())
+            .apply(&op.a1 * f64::NAN, "nan")
+            .build(),
+        Err(rsrs::Error::InvalidQuery(_))
+    ));
+    assert!(matches!(
+        Demo::aggregate("my_index", Expr::all())
+            .filter(ApplyExpr::<Num>::from(&op.a1).lt(f64::INFINITY))
+            .build(),
+        Err(rsrs::Error::InvalidQuery(_))
+    ));
+    assert!(Demo::aggregate("my_index", Expr::all())
+        .apply(upper(substr(&op.description, 0, 3)), "up")
+        .build()
+        .is_ok());
+    assert_eq!(timefmt(&op.a10, None).as_str(), "timefmt(@a10)");
+    assert_eq!(
+        timefmt(day(&op.a10), Some("%Y-%m-%d")).as_str(),
+        "timefmt(day(@a10), \"%Y-%m-%d\")"
+    );
+    assert_eq!(
+        upper(substr(&op.description, 0, 3)).as_str(),
+        "upper(substr(@description, 0, 3))"
+    );
+    assert_eq!(
+        format(
+            "%s-%s",
+            vec![(&op.description).into(), hour(&op.a10).into()]
+        )
+        .as_str(),
+        "format(\"%s-%s\", @description, hour(@a10))"
+    );
+    assert_eq!(
+        ApplyExpr::<Str>::from(&op.description)
+            .eq("say \"hi\"")
+            .and(ApplyExpr::<Num>::from(&op.a1).gt(1).or(exists(&op.a2)))
+            .as_str(),
+        "@description == \"say \\\"hi\\\"\" && (@a1 > 1 || exists(@a2))"
+    );
+    assert_eq!(
+        (!startswith(lower(&op.description), "a")).as_str(),
+        "!startswith(lower(@description), \"a\")"
+    );
+    assert_eq!(
+        geodistance(
+            ApplyExpr::<Geo>::property("location"),
+            ApplyExpr::point(1.5, -2.)
+        )
+        .as_str(),
+        "geodistance(@location, \"1.5,-2\")"
+    );
+
+    assert_eq!(
+        to_redis_string(
+            &Demo::aggregate("my_index", Expr::all())
+                .apply(&op.a13 * &op.a14, "total")
+                .filter(ApplyExpr::<Num>::property("total").ge(100))
+                .build()
+                .unwrap()
+        )
+        .as_str(),
+        "my_index * APPLY @a13 * @a14 AS total FILTER @total >= 100"
+    );
+}
+
+#[derive(Clone, Default)]
+struct MockConnection {
+    replies: Arc<Mutex<VecDeque<redis::Value>>>,
+    commands: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockConnection {
+    fn new(replies: Vec<redis::Value>) -> Self {
+        MockConnection {
+            replies: Arc::new(Mutex::new(replies.into())),
+            commands: Default::default(),
+        }
+    }
+
+    fn commands(&self) -> Vec<String> {
+        self.commands.lock().unwrap().clone()
+    }
+
+    /// Records `command` and pops its canned reply, `Status("-...")` standing
+    /// for an error reply.
+    fn reply(&self, command: String) -> redis::RedisResult<redis::Value> {
+        self.commands.lock().unwrap().push(command);
+        match self.replies.lock().unwrap().pop_front() {
+            Some(redis::Value::Status(err)) if err.starts_with('-') => {
+                redis::parse_redis_value(format!("{err}\r\n").as_bytes())
+            }
+            Some(reply) => Ok(reply),
+            None => Err(redis::RedisError::from((
+                redis::ErrorKind::ClientError,
+                "no reply left in the mock",
+            ))),
+        }
+    }
+
+    fn replies(
+        &self,
+        commands: Vec<String>,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisResult<Vec<redis::Value>> {
+        let replies = commands
+            .into_iter()
+            .map(|command| self.reply(command))
+            .collect::<redis::RedisResult<Vec<_>>>()?;
+        Ok(replies.into_iter().skip(offset).take(count).collect())
+    }
+}
+
+fn command_string(cmd: &redis::Cmd) -> String {
+    cmd.args_iter()
+        .map(|arg| match arg {
+            redis::Arg::Simple(arg) => String::from_utf8_lossy(arg).to_string(),
+            redis::Arg::Cursor => "<cursor>".to_string(),
+        })
+        .join(" ")
+}
+
+/// Splits packed RESP commands into their space separated arguments.
+fn unpack_commands(mut packed: &[u8]) -> Vec<String> {
+    let mut commands = vec![];
+    while !packed.is_empty() {
+        let mut args = vec![];
+        let end = packed.iter().position(|x| *x == b'\r').unwrap();
+        let count: usize = std::str::from_utf8(&packed[1..end])
+            .unwrap()
+            .parse()
+            .unwrap();
+        packed = &packed[end + 2..];
+        for _ in 0..count {
+            let end = packed.iter().position(|x| *x == b'\r').unwrap();
+            let len: usize = std::str::from_utf8(&packed[1..end])
+                .unwrap()
+                .parse()
+                .unwrap();
+            args.push(String::from_utf8_lossy(&packed[end + 2..end + 2 + len]).to_string());
+            packed = &packed[end + 2 + len + 2..];
+        }
+        commands.push(args.join(" "));
+    }
+    commands
+}
+
+impl redis::aio::ConnectionLike for MockConnection {
+    fn req_packed_command<'a>(
+        &'a mut self,
+        cmd: &'a redis::Cmd,
+    ) -> redis::RedisFuture<'a, redis::Value> {
+        let reply = self.reply(command_string(cmd));
+        Box::pin(async move { reply })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisFuture<'a, Vec<redis::Value>> {
+        let replies = self.replies(cmd.cmd_iter().map(command_string).collect(), offset, count);
+        Box::pin(async move { replies })
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
+
+impl redis::ConnectionLike for MockConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> redis::RedisResult<redis::Value> {
+        let command = unpack_commands(cmd).join(" ");
+        self.reply(command)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisResult<Vec<redis::Value>> {
+        self.replies(unpack_commands(cmd), offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+fn data(s: &str) -> redis::Value {
+    redis::Value::Data(s.as_bytes().to_vec())
+}
+
+fn bulk(values: Vec<redis::Value>) -> redis::Value {
+    redis::Value::Bulk(values)
+}
+
+fn cursor_reply(rows: &[&str], cursor_id: i64) -> redis::Value {
+    let mut page = vec![redis::Value::Int(3)];
+    page.extend(rows.iter().map(|x| bulk(vec![data("a1"), data(x)])));
+    bulk(vec![bulk(page), redis::Value::Int(cursor_id)])
+}
+
+#[tokio::test]
+async fn test_aggregate_cursor() {
+    let op = Demo::op();
+    let aggregate = Demo::aggregate("my_index", Expr::all())
+        .group_by(&[&op.a1], vec![])
+        .with_cursor(Some(2), Some(1000))
+        .build()
+        .unwrap();
+
+    let conn = MockConnection::new(vec![cursor_reply(&["1", "2"], 42), cursor_reply(&["3"], 0)]);
+    let rows: Vec<HashMap<String, String>> = AggregateCursor::new(conn.clone(), aggregate)
+        .map(|x| x.unwrap())
+        .collect()
+        .await;
+    assert_eq!(
+        rows.iter().map(|x| x["a1"].as_str()).collect_vec(),
+        vec!["1", "2", "3"]
+    );
+    assert_eq!(
+        conn.commands(),
+        vec![
+            "FT.AGGREGATE my_index * GROUPBY 1 @a1 WITHCURSOR COUNT 2 MAXIDLE 1000",
+            "FT.CURSOR READ my_index 42 COUNT 2",
+        ]
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_aggregate_cursor_drop() {
+    let conn = MockConnection::new(vec![cursor_reply(&["1", "2"], 42), redis::Value::Okay]);
+    let mut cursor = AggregateCursor::<_, HashMap<String, String>>::new(
+        conn.clone(),
+        Demo::aggregate("my_index", Expr::all()).build().unwrap(),
+    );
+    assert_eq!(cursor.next().await.unwrap().unwrap()["a1"], "1");
+    assert_eq!(cursor.cursor_id(), 42);
+    drop(cursor);
+    tokio::task::yield_now().await;
+
+    assert_eq!(
+        conn.commands(),
+        vec![
+            "FT.AGGREGATE my_index * WITHCURSOR",
+            "FT.CURSOR DEL my_index 42",
+        ]
+    );
+}
+
+#[test]
+fn test_search_results() {
+    let reply = bulk(vec![
+        redis::Value::Int(2),
+        data("doc:1"),
+        bulk(vec![
+            data("a1"),
+            data("1"),
+            data("description"),
+            data("aaa"),
+        ]),
+        data("doc:2"),
+        bulk(vec![data("a1"), data("2")]),
+    ]);
+    let search = Demo::search("my_index", Demo::op().a1.in_range(..))
+        .build()
+        .unwrap();
+    let results: SearchResults<HashMap<String, String>> =
+        SearchResults::from_reply(reply, &search.option).unwrap();
+    assert_eq!(results.total, 2);
+    assert_eq!(results.hits.len(), 2);
+    assert_eq!(results.hits[0].key, "doc:1");
+    assert_eq!(results.hits[0].score, None);
+    assert_eq!(
+        results.hits[0].document.as_ref().unwrap()["description"],
+        "aaa"
+    );
+    assert_eq!(results.hits[1].document.as_ref().unwrap()["a1"], "2");
+
+    let reply = bulk(vec![
+        redis::Value::Int(5),
+        data("doc:1"),
+        data("1.5"),
+        data("doc:2"),
+        data("0.5"),
+    ]);
+    let search = Demo::search("my_index", Demo::op().a1.in_range(..))
+        .no_content()
+        .with_scores()
+        .build()
+        .unwrap();
+    let results: SearchResults<HashMap<String, String>> =
+        SearchResults::from_reply(reply, &search.option).unwrap();
+    assert_eq!(results.total, 5);
+    assert_eq!(
+        results
+            .hits
+            .iter()
+            .map(|x| (x.key.as_str(), x.score, x.document.is_none()))
+            .collect_vec(),
+        vec![("doc:1", Some(1.5), true), ("doc:2", Some(0.5), true)]
+    );
+
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("doc:1"),
+        bulk(vec![data("2"), bulk(vec![data("explanation")])]),
+        data("payload"),
+        data("#12"),
+        bulk(vec![data("a1"), data("1")]),
+    ]);
+    let search = Demo::search("my_index", Demo::op().a1.in_range(..))
+        .explain_score()
+        .with_payloads()
+        .with_sort_keys()
+        .build()
+        .unwrap();
+    let results: SearchResults<HashMap<String, String>> = search.parse_reply(reply).unwrap();
+    let hit = &results.hits[0];
+    assert_eq!(hit.score, Some(2.));
+    assert_eq!(hit.payload.as_deref(), Some(b"payload".as_slice()));
+    assert_eq!(hit.sort_key.as_deref(), Some("#12"));
+    assert_eq!(hit.document.as_ref().unwrap()["a1"], "1");
+
+    let reply = bulk(vec![redis::Value::Int(1), data("doc:1")]);
+    assert!(SearchResults::<HashMap<String, String>>::from_reply(reply, &search.option).is_err());
+}
+
+#[allow(dead_code)]
+#[derive(Document)]
+#[document(decode)]
+struct DecodeDemo {
+    #[field(type = "text")]
+    description: String,
+    #[field(type = "vector", flat, f32, dim = 2, distance_metric=l2)]
+    vector32: Vec<f32>,
+    #[field(type = "vector", flat, f64, dim = 1, distance_metric=l2)]
+    vector64: Vec<f64>,
+    #[field(type = "number")]
+    a1: i8,
+    #[field(type = "number")]
+    a2: i16,
+    #[field(type = "number")]
+    a3: i32,
+    #[field(type = "number")]
+    a4: i64,
+    #[field(type = "number")]
+    a5: i128,
+    #[field(type = "number")]
+    a6: isize,
+    #[field(type = "number")]
+    a7: u8,
+    #[field(type = "number")]
+    a8: u16,
+    #[field(type = "number")]
+    a9: u32,
+    #[field(type = "number")]
+    a10: u64,
+    #[field(type = "number")]
+    a11: u128,
+    #[field(type = "number")]
+    a12: usize,
+    #[field(type = "number")]
+    a13: f32,
+    #[field(type = "number")]
+    a14: f64,
+    cache: Vec<String>,
+}
+
+/// Members without `#[field]` need no Default unless decoding is derived.
+#[allow(dead_code)]
+#[derive(Document)]
+struct Session {
+    #[field(type = "text")]
+    user: String,
+    opened: std::time::Instant,
+}
+
+#[test]
+fn test_decode_document() {
+    let vector32: &[u8] = bytemuck::cast_slice(&[1f32, 2.]);
+    let vector64: &[u8] = bytemuck::cast_slice(&[3f64]);
+    let mut fields = vec![
+        bulk(vec![data("description"), data("aaa")]),
+        bulk(vec![
+            data("vector32"),
+            redis::Value::Data(vector32.to_vec()),
+        ]),
+        bulk(vec![
+            data("vector64"),
+            redis::Value::Data(vector64.to_vec()),
+        ]),
+    ];
+    for (i, value) in [
+        "-1", "-2", "-3", "-4", "-5", "-6", "7", "8", "9", "10", "11", "12", "1.5", "-2.5",
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        fields.push(bulk(vec![data(&format!("a{}", i + 1)), data(value)]));
+    }
+    let fields = fields
+        .into_iter()
+        .flat_map(|x| match x {
+            redis::Value::Bulk(x) => x,
+            _ => unreachable!(),
+        })
+        .collect_vec();
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("doc:1"),
+        bulk(fields.clone()),
+    ]);
+    let search = DecodeDemo::search("my_index", Expr::all()).build().unwrap();
+    let results: SearchResults<DecodeDemo> = search.parse_reply(reply).unwrap();
+    let demo = results.hits[0].document.as_ref().unwrap();
+    assert_eq!(demo.description, "aaa");
+    assert_eq!(demo.vector32, vec![1., 2.]);
+    assert_eq!(demo.vector64, vec![3.]);
+    assert_eq!(
+        (demo.a1, demo.a2, demo.a3, demo.a4, demo.a5, demo.a6),
+        (-1, -2, -3, -4, -5, -6)
+    );
+    assert_eq!(
+        (demo.a7, demo.a8, demo.a9, demo.a10, demo.a11, demo.a12),
+        (7, 8, 9, 10, 11, 12)
+    );
+    assert_eq!((demo.a13, demo.a14), (1.5, -2.5));
+    assert!(demo.cache.is_empty());
+
+    let mut bad = fields.clone();
+    let a7 = bad.iter().position(|x| x == &data("a7")).unwrap();
+    bad[a7 + 1] = data("300");
+    let reply = bulk(vec![redis::Value::Int(1), data("doc:1"), bulk(bad)]);
+    match search.parse_reply::<DecodeDemo>(reply) {
+        Err(rsrs::Error::FieldDecode { field, .. }) => assert_eq!(field, "a7"),
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+
+    let mut bad = fields.clone();
+    let vector64 = bad.iter().position(|x| x == &data("vector64")).unwrap();
+    bad[vector64 + 1] = redis::Value::Data(vec![0; 12]);
+    let reply = bulk(vec![redis::Value::Int(1), data("doc:1"), bulk(bad)]);
+    match search.parse_reply::<DecodeDemo>(reply) {
+        Err(rsrs::Error::FieldDecode { field, .. }) => assert_eq!(field, "vector64"),
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("doc:1"),
+        bulk(fields[2..].to_vec()),
+    ]);
+    match search.parse_reply::<DecodeDemo>(reply) {
+        Err(err) => assert_eq!(
+            err.to_string(),
+            "failed to decode field `description`: missing from reply"
+        ),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Document, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+struct JsonDemo {
+    #[field(type = "text")]
+    title: String,
+    #[field(type = "number")]
+    price: f64,
+    #[field(type = "vector", flat, f32, dim = 2, distance_metric=l2)]
+    embedding: Vec<f32>,
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_decode_json() {
+    let search = JsonDemo::search("my_index", Expr::all()).build().unwrap();
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("doc:1"),
+        bulk(vec![
+            data("$"),
+            data(r#"{"title":"aaa","price":1.5,"embedding":[1.0,2.0]}"#),
+        ]),
+    ]);
+    let results: SearchResults<Json<JsonDemo>> = search.parse_reply(reply).unwrap();
+    assert_eq!(
+        results.hits[0].document.as_ref().unwrap().0,
+        JsonDemo {
+            title: "aaa".to_string(),
+            price: 1.5,
+            embedding: vec![1., 2.],
+        }
+    );
+
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("doc:1"),
+        bulk(vec![
+            data("$"),
+            data(r#"[{"title":"aaa","price":1.5,"embedding":[1.0,2.0]}]"#),
+        ]),
+    ]);
+    let results: SearchResults<Json<JsonDemo>> = search.parse_reply(reply).unwrap();
+    assert_eq!(results.hits[0].document.as_ref().unwrap().title, "aaa");
+
+    let op = JsonDemo::op();
+    let search = JsonDemo::search("my_index", Expr::all())
+        .return_fields(&[&op.title])
+        .return_as("$.price", "price")
+        .return_as("$.embedding", "embedding")
+        .build()
+        .unwrap();
+    assert_eq!(
+        to_redis_string(&search),
+        "my_index * RETURN 7 title $.price AS price $.embedding AS embedding"
+    );
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("doc:1"),
+        bulk(vec![
+            data("title"),
+            data("aaa"),
+            data("price"),
+            data("1.5"),
+            data("$.embedding"),
+            data("[1.0,2.0]"),
+        ]),
+    ]);
+    let results: SearchResults<Json<JsonDemo>> = search.parse_reply(reply).unwrap();
+    assert_eq!(
+        results.hits[0].document.as_ref().unwrap().embedding,
+        vec![1., 2.]
+    );
+
+    // Attributes are kept as strings, whatever they look like.
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("doc:1"),
+        bulk(vec![
+            data("title"),
+            data("123"),
+            data("price"),
+            data("1.5"),
+            data("embedding"),
+            data("[1.0,2.0]"),
+        ]),
+    ]);
+    let results: SearchResults<Json<JsonDemo>> = search.parse_reply(reply).unwrap();
+    assert_eq!(results.hits[0].document.as_ref().unwrap().title, "123");
+
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("doc:1"),
+        bulk(vec![data("title"), data("aaa")]),
+    ]);
+    match search.parse_reply::<Json<JsonDemo>>(reply) {
+        Err(rsrs::Error::FieldDecode { field, reason }) => {
+            assert_eq!(field, "$");
+            assert!(reason.contains("price"));
+        }
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_save_document() {
+    let doc = JsonDemo {
+        title: "say \"hi\"".to_string(),
+        price: 1.5,
+        embedding: vec![1., 2.],
+    };
+    let hash = doc.save_as("doc:1", &IndexOption::default());
+    assert_eq!(hash.command(), "HSET");
+    assert_eq!(
+        to_redis_string(&hash),
+        "doc:1 title say \"hi\" price 1.5 embedding <8Bytes>"
+    );
+    match &hash {
+        DocumentWrite::HSet { fields, .. } => {
+            assert_eq!(
+                fields[2].1,
+                [1f32.to_le_bytes(), 2f32.to_le_bytes()].concat()
+            )
+        }
+        _ => unreachable!(),
+    }
+
+    let json = doc.save_as(
+        "doc:1",
+        &IndexOption {
+            on: DataType::Json.into(),
+            ..Default::default()
+        },
+    );
+    assert_eq!(json.command(), "JSON.SET");
+    assert_eq!(
+        to_redis_string(&json),
+        r#"doc:1 $ {"title":"say \"hi\"","price":1.5,"embedding":[1,2]}"#
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        let results: SearchResults<Json<JsonDemo>> = JsonDemo::search("my_index", Expr::all())
+            .build()
+            .unwrap()
+            .parse_reply(bulk(vec![
+                redis::Value::Int(1),
+                data("doc:1"),
+                bulk(vec![
+                    data("$"),
+                    data(&to_redis_string(&json)["doc:1 $ ".len()..]),
+                ]),
+            ]))
+            .unwrap();
+        assert_eq!(results.hits[0].document.as_ref().unwrap().0, doc);
+    }
+
+    let DocumentWrite::HSet { fields, .. } = hash else {
+        unreachable!()
+    };
+    let fields = fields
+        .into_iter()
+        .flat_map(|(k, v)| [data(k), redis::Value::Data(v)])
+        .collect_vec();
+    let results: SearchResults<HashMap<String, redis::Value>> =
+        JsonDemo::search("my_index", Expr::all())
+            .build()
+            .unwrap()
+            .parse_reply(bulk(vec![
+                redis::Value::Int(1),
+                data("doc:1"),
+                bulk(fields),
+            ]))
+            .unwrap();
+    assert_eq!(results.hits[0].document.as_ref().unwrap().len(), 3);
+}
+
+#[derive(Document)]
+struct Note {
+    #[field(type = "text")]
+    title: String,
+    #[field(type = "text")]
+    body: Option<String>,
+}
+
+#[test]
+fn test_save_removed_field() {
+    let note = Note {
+        title: "aaa".to_string(),
+        body: None,
+    };
+    let hash = note.save_as("note:1", &IndexOption::default());
+    assert_eq!(to_redis_string(&hash), "note:1 title aaa");
+    let mut con = MockConnection::new(vec![
+        redis::Value::Okay,
+        redis::Value::Status("QUEUED".to_string()),
+        redis::Value::Status("QUEUED".to_string()),
+        bulk(vec![redis::Value::Int(1), redis::Value::Int(1)]),
+    ]);
+    let _: () = hash.to_pipeline().query(&mut con).unwrap();
+    assert_eq!(
+        con.commands(),
+        ["MULTI", "HSET note:1 title aaa", "HDEL note:1 body", "EXEC"]
+    );
+
+    let json = note.save_as(
+        "note:1",
+        &IndexOption {
+            on: DataType::Json.into(),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        to_redis_string(&json),
+        r#"note:1 $ {"title":"aaa","body":null}"#
+    );
+    let mut con = MockConnection::new(vec![
+        redis::Value::Okay,
+        redis::Value::Status("QUEUED".to_string()),
+        bulk(vec![redis::Value::Okay]),
+    ]);
+    let _: () = json.to_pipeline().query(&mut con).unwrap();
+    assert_eq!(
+        con.commands(),
+        [
+            "MULTI",
+            r#"JSON.SET note:1 $ {"title":"aaa","body":null}"#,
+            "EXEC"
+        ]
+    );
+
+    let merge = DocumentWrite::merge(
+        "note:1",
+        &IndexOption {
+            on: DataType::Json.into(),
+            ..Default::default()
+        },
+        &note.fields(),
+    );
+    assert_eq!(merge.command(), "JSON.MERGE");
+    assert_eq!(
+        to_redis_string(&merge),
+        r#"note:1 $ {"title":"aaa","body":null}"#
+    );
+    assert_eq!(
+        DocumentWrite::merge("note:1", &IndexOption::default(), &note.fields()).command(),
+        "HSET"
+    );
+}
+
+#[allow(dead_code)]
+#[derive(Document, Debug, PartialEq)]
+#[document(prefix = "user:", decode)]
+struct User {
+    #[id]
+    id: u64,
+    #[field(type = "text")]
+    name: String,
+}
+
+#[test]
+fn test_document_key() {
+    let user = User {
+        id: 42,
+        name: "aaa".to_string(),
+    };
+    assert_eq!(User::key_prefix(), Some("user:"));
+    assert_eq!(user.key().as_deref(), Some("user:42"));
+    assert_eq!(
+        to_redis_string(&user.save(&IndexOption::default()).unwrap()),
+        "user:42 name aaa"
+    );
+    assert!(matches!(
+        Demo {
+            description: Default::default(),
+            vector32: Default::default(),
+            vector64: Default::default(),
+            a1: 0,
+            a2: 0,
+            a3: 0,
+            a4: 0,
+            a5: 0,
+            a6: 0,
+            a7: 0,
+            a8: 0,
+            a9: 0,
+            a10: 0,
+            a11: 0,
+            a12: 0,
+            a13: 0.,
+            a14: 0.,
+        }
+        .save(&IndexOption::default()),
+        Err(rsrs::Error::MissingId)
+    ));
+
+    assert_eq!(
+        to_redis_string(&User::create_index("users", IndexOption::default())),
+        "users PREFIX 1 user: SCHEMA name TEXT"
+    );
+    assert_eq!(
+        to_redis_string(&User::create_index(
+            "users",
+            IndexOption {
+                prefix: Some(vec!["user".to_string()]),
+                ..Default::default()
+            }
+        )),
+        "users PREFIX 1 user SCHEMA name TEXT"
+    );
+    assert_eq!(
+        to_redis_string(&User::create_index(
+            "users",
+            IndexOption {
+                prefix: Some(vec!["admin:".to_string()]),
+                ..Default::default()
+            }
+        )),
+        "users PREFIX 2 admin: user: SCHEMA name TEXT"
+    );
+
+    let search = User::search("users", Expr::all()).build().unwrap();
+    let reply = bulk(vec![
+        redis::Value::Int(2),
+        data("user:42"),
+        bulk(vec![data("name"), data("aaa")]),
+        data("admin:1"),
+        bulk(vec![data("name"), data("bbb")]),
+    ]);
+    let err = search.parse_reply::<User>(reply).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to decode field `id`: key 'admin:1' does not start with 'user:'"
+    );
+    let reply = bulk(vec![
+        redis::Value::Int(1),
+        data("user:42"),
+        bulk(vec![data("name"), data("aaa")]),
+    ]);
+    let results: SearchResults<User> = search.parse_reply(reply).unwrap();
+    assert_eq!(results.hits[0].document.as_ref().unwrap(), &user);
+}
+
+#[tokio::test]
+async fn test_async_commands() {
+    use rsrs::AsyncSearchCommands;
+
+    let mut conn = MockConnection::new(vec![
+        redis::Value::Okay,
+        redis::Value::Status("-ERR Index already exists".to_string()),
+        bulk(vec![
+            redis::Value::Int(1),
+            data("user:42"),
+            bulk(vec![data("name"), data("aaa")]),
+        ]),
+        bulk(vec![
+            redis::Value::Int(1),
+            bulk(vec![data("name"), data("aaa"), data("count"), data("2")]),
+        ]),
+        bulk(vec![
+            data("index_name"),
+            data("users"),
+            data("num_docs"),
+            data("3"),
+            data("indexing"),
+            redis::Value::Int(0),
+            data("percent_indexed"),
+            data("1"),
+        ]),
+        redis::Value::Status("-ERR Unknown Index name".to_string()),
+        redis::Value::Status("-ERR Syntax error at offset 1 near users".to_string()),
+    ]);
+    let create = User::create_index("users", IndexOption::default());
+    conn.ft_create(&create).await.unwrap();
+    assert!(matches!(
+        conn.ft_create(&create).await,
+        Err(rsrs::Error::IndexAlreadyExists)
+    ));
+
+    let search = User::search("users", Expr::all()).build().unwrap();
+    let results: SearchResults<User> = conn.ft_search(&search).await.unwrap();
+    assert_eq!(results.hits[0].document.as_ref().unwrap().id, 42);
+
+    let aggregate = User::aggregate("users", Expr::all())
+        .group_by(&[&User::op().name], vec![Reducer::count().alias("count")])
+        .build()
+        .unwrap();
+    let results: AggregateResults<HashMap<String, String>> =
+        conn.ft_aggregate(&aggregate).await.unwrap();
+    assert_eq!(results.total, 1);
+    assert_eq!(results.rows[0]["count"], "2");
+
+    let info = conn.ft_info("users").await.unwrap();
+    assert_eq!(info.index_name, "users");
+    assert_eq!(info.num_docs, 3);
+    assert!(!info.indexing);
+    assert_eq!(info.percent_indexed, 1.);
+
+    assert!(matches!(
+        conn.ft_drop("users", true).await,
+        Err(rsrs::Error::UnknownIndex)
+    ));
+    assert!(matches!(
+        conn.ft_search::<User>(&search).await,
+        Err(rsrs::Error::QuerySyntax(_))
+    ));
+
+    assert_eq!(
+        conn.commands(),
+        vec![
+            "FT.CREATE users PREFIX 1 user: SCHEMA name TEXT",
+            "FT.CREATE users PREFIX 1 user: SCHEMA name TEXT",
+            "FT.SEARCH users *",
+            "FT.AGGREGATE users * GROUPBY 1 @name REDUCE COUNT 0 AS count",
+            "FT.INFO users",
+            "FT.DROPINDEX users DD",
+            "FT.SEARCH users *",
+        ]
+    );
+}
+
+#[test]
+fn test_blocking_commands() {
+    use rsrs::*;
+
+    let mut conn = MockConnection::new(vec![
+        redis::Value::Okay,
+        bulk(vec![
+            redis::Value::Int(1),
+            data("user:42"),
+            bulk(vec![data("name"), data("aaa")]),
+        ]),
+        bulk(vec![
+            redis::Value::Int(1),
+            bulk(vec![data("count"), data("2")]),
+        ]),
+        bulk(vec![
+            data("index_name"),
+            data("users"),
+            data("num_docs"),
+            data("3"),
+        ]),
+        redis::Value::Status("-ERR Unknown Index name".to_string()),
+    ]);
+    let create = User::create_index("users", IndexOption::default());
+    conn.ft_create_blocking(&create).unwrap();
+
+    let search = User::search("users", Expr::all()).build().unwrap();
+    let results: SearchResults<User> = conn.ft_search_blocking(&search).unwrap();
+    assert_eq!(results.hits[0].key, "user:42");
+
+    let aggregate = User::aggregate("users", Expr::all())
+        .group_by(&[], vec![Reducer::count().alias("count")])
+        .build()
+        .unwrap();
+    let results: AggregateResults<HashMap<String, usize>> =
+        conn.ft_aggregate_blocking(&aggregate).unwrap();
+    assert_eq!(results.rows[0]["count"], 2);
+
+    assert_eq!(conn.ft_info_blocking("users").unwrap().num_docs, 3);
+    assert!(matches!(
+        conn.ft_drop_blocking("users", false),
+        Err(rsrs::Error::UnknownIndex)
+    ));
+
+    assert_eq!(
+        conn.commands(),
+        vec![
+            "FT.CREATE users PREFIX 1 user: SCHEMA name TEXT",
+            "FT.SEARCH users *",
+            "FT.AGGREGATE users * GROUPBY 0 REDUCE COUNT 0 AS count",
+            "FT.INFO users",
+            "FT.DROPINDEX users",
+        ]
+    );
+
+    let mut conn = MockConnection::new(vec![
+        redis::Value::Status("-Unknown Index name".to_string()),
+        redis::Value::Status("-users: no such index".to_string()),
+        redis::Value::Status("-ERR unknown command 'FT.INFO', no such index".to_string()),
+        redis::Value::Status("-ERR Unknown argument `Index already exists`".to_string()),
+    ]);
+    for _ in 0..2 {
+        assert!(matches!(
+            conn.ft_info_blocking("users"),
+            Err(rsrs::Error::UnknownIndex)
+        ));
+    }
+    for _ in 0..2 {
+        assert!(matches!(
+            conn.ft_info_blocking("users"),
+            Err(rsrs::Error::Redis(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_index() {
+    let index: Index<User> = Index::new("users", IndexOption::default()).unwrap();
+    assert_eq!(index.name(), "users");
+    assert_eq!(index.option().prefix, Some(vec!["user:".to_string()]));
+    assert_eq!(index.schema().len(), 1);
+
+    let mut conn = MockConnection::new(vec![
+        redis::Value::Status("-ERR Unknown Index name".to_string()),
+        redis::Value::Okay,
+        bulk(vec![data("index_name"), data("users")]),
+        bulk(vec![
+            redis::Value::Int(1),
+            data("user:42"),
+            bulk(vec![data("name"), data("aaa")]),
+        ]),
+        bulk(vec![
+            redis::Value::Int(1),
+            bulk(vec![data("count"), data("1")]),
+        ]),
+        redis::Value::Okay,
+    ]);
+    assert!(index.ensure_exists(&mut conn).await.unwrap());
+    assert!(!index.ensure_exists(&mut conn).await.unwrap());
+    let results = index
+        .search(&mut conn, User::op().name.contains(&["aaa"]).limit(0, 10))
+        .await
+        .unwrap();
+    assert_eq!(results.hits[0].document.as_ref().unwrap().id, 42);
+    let results: AggregateResults<HashMap<String, usize>> = index
+        .aggregate(&mut conn, Expr::all(), |x| {
+            x.group_by(&[], vec![Reducer::count().alias("count")])
+        })
+        .await
+        .unwrap();
+    assert_eq!(results.rows[0]["count"], 1);
+    index.drop(&mut conn, false).await.unwrap();
+
+    assert_eq!(
+        conn.commands(),
+        vec![
+            "FT.INFO users",
+            "FT.CREATE users PREFIX 1 user: SCHEMA name TEXT",
+            "FT.INFO users",
+            "FT.SEARCH users @name:aaa LIMIT 0 10",
+            "FT.AGGREGATE users * GROUPBY 0 REDUCE COUNT 0 AS count",
+            "FT.DROPINDEX users",
+        ]
+    );
+
+    let mut conn = MockConnection::new(vec![
+        redis::Value::Status("-ERR Unknown Index name".to_string()),
+        redis::Value::Status("-ERR Index already exists".to_string()),
+    ]);
+    assert!(!index.ensure_exists_blocking(&mut conn).unwrap());
+}
+
+#[cfg(feature = "serde")]
+#[derive(Document, serde::Deserialize, Debug, PartialEq)]
+#[document(prefix = "book:", on = json, decode)]
+struct Book {
+    #[id]
+    #[serde(default)]
+    isbn: String,
+    #[field(type = "text")]
+    title: String,
+    #[field(type = "number")]
+    price: f64,
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_index_search() {
+    let mut conn = MockConnection::new(vec![bulk(vec![
+        redis::Value::Int(1),
+        data("book:42"),
+        bulk(vec![data("$"), data(r#"{"title":"123","price":1.5}"#)]),
+    ])]);
+    let results = Index::<Book>::declared()
+        .unwrap()
+        .search_blocking(&mut conn, Expr::all())
+        .unwrap();
+    assert_eq!(
+        results.hits[0].document.as_ref().unwrap(),
+        &Book {
+            isbn: "42".to_string(),
+            title: "123".to_string(),
+            price: 1.5,
+        }
+    );
+    assert_eq!(conn.commands(), ["FT.SEARCH book *"]);
+
+    assert!(matches!(
+        Index::<User>::new(
+            "users",
+            IndexOption {
+                on: DataType::Json.into(),
+                ..Default::default()
+            },
+        ),
+        Err(rsrs::Error::InvalidQuery(_))
+    ));
+    assert!(matches!(
+        Index::<Book>::new(
+            "books",
+            IndexOption {
+                on: DataType::Hash.into(),
+                ..Default::default()
+            },
+        ),
+        Err(rsrs::Error::InvalidQuery(_))
+    ));
+}
+
+#[allow(dead_code)]
+#[derive(Document)]
+#[document(index = "products", on = json, prefix = ["product:", "item:"], language = "english", score = 0.5)]
+struct Product {
+    #[id]
+    sku: String,
+    #[field(type = "text")]
+    title: String,
+}
+
+#[test]
+fn test_document_option() {
+    assert_eq!(Product::index_name(), "products");
+    assert_eq!(User::index_name(), "user");
+    assert_eq!(
+        to_redis_string(&Product::index_option()),
+        "ON JSON PREFIX 2 product: item: LANGUAGE english SCORE 0.5"
+    );
+    assert_eq!(
+        Product {
+            sku: "a1".to_string(),
+            title: "aaa".to_string(),
+        }
+        .key()
+        .as_deref(),
+        Some("product:a1")
+    );
+
+    let index: Index<Product> = Index::declared().unwrap();
+    assert_eq!(
+        to_redis_string(index.ft_create()),
+        "products ON JSON PREFIX 2 product: item: LANGUAGE english SCORE 0.5 SCHEMA $.title AS title TEXT"
+    );
+
+    assert_eq!(
+        to_redis_string(&Product::create_index(
+            "products_v2",
+            IndexOption {
+                on: DataType::Hash.into(),
+                score: Some(1.),
+                ..Default::default()
+            }
+        )),
+        "products_v2 ON HASH PREFIX 2 product: item: LANGUAGE english SCORE 1 SCHEMA title TEXT"
+    );
+}
+
+#[allow(dead_code)]
+#[derive(Document)]
+#[document(
+    prefix = "log:",
+    language_field = "lang",
+    score_field = "rank",
+    payload_field = "extra",
+    max_text_fields,
+    temporary = 3600,
+    no_offsets,
+    no_hl,
+    no_fields,
+    no_freqs,
+    stopwords = [],
+    skip_initial_scan
+)]
+struct LogLine {
+    #[field(type = "text")]
+    message: String,
+}
+
+#[allow(dead_code)]
+#[derive(Document)]
+#[document(score = 1)]
+struct Scored {
+    #[field(type = "text")]
+    message: String,
+}
+
+#[test]
+fn test_index_option() {
+    assert_eq!(
+        to_redis_string(&LogLine::create_index("logs", IndexOption::default())),
+        "logs PREFIX 1 log: LANGUAGE_FIELD lang SCORE_FIELD rank PAYLOAD_FIELD extra MAXTEXTFIELDS TEMPORARY 3600 NOOFFSETS NOHL NOFIELDS NOFREQS STOPWORDS 0 SKIPINITIALSCAN SCHEMA message TEXT"
+    );
+    assert_eq!(
+        to_redis_string(&LogLine::create_index(
+            "logs",
+            IndexOption {
+                max_text_fields: Some(false),
+                no_offsets: Some(false),
+                no_hl: Some(false),
+                no_fields: Some(false),
+                no_freqs: Some(false),
+                skip_initial_scan: Some(false),
+                ..Default::default()
+            }
+        )),
+        "logs PREFIX 1 log: LANGUAGE_FIELD lang SCORE_FIELD rank PAYLOAD_FIELD extra TEMPORARY 3600 STOPWORDS 0 SCHEMA message TEXT"
+    );
+    assert_eq!(
+        to_redis_string(&User::create_index(
+            "users",
+            IndexOption {
+                no_hl: Some(true),
+                ..Default::default()
+            }
+        )),
+        "users PREFIX 1 user: NOHL SCHEMA name TEXT"
+    );
+    assert_eq!(to_redis_string(&Scored::index_option()), "SCORE 1");
+    assert_eq!(
+        to_redis_string(&IndexOption {
+            on: DataType::Hash.into(),
+            language: Some(Language::Chinese),
+            score: Some(0.5),
+            stopwords: Some(vec!["a".to_string(), "the".to_string()]),
+            ..Default::default()
+        }),
+        "ON HASH LANGUAGE chinese SCORE 0.5 STOPWORDS 2 a the"
+    );
+}
+
+#[test]
+fn test_language() {
+    assert_eq!("English".parse::<Language>(), Ok(Language::English));
+    assert_eq!(Language::Chinese.as_str(), "chinese");
+    assert!("klingon".parse::<Language>().is_err());
+    assert_eq!(Language::ALL.len(), 29);
+}
+
+#[allow(dead_code)]
+#[derive(Document)]
+#[document(prefix = "p:", filter = "@kind == \"product\"")]
+struct Listing {
+    #[field(type = "text")]
+    kind: String,
+    #[field(type = "number")]
+    price: f64,
+}
+
+#[test]
+fn test_index_filter() {
+    let op = Listing::op();
+    let filter = ApplyExpr::from(&op.kind)
+        .eq("product")
+        .and(startswith(ApplyExpr::key(), "p:"))
+        .and(
+            ApplyExpr::score()
+                .gt(0.5)
+                .or(ApplyExpr::from(&op.price).lt(10)),
+        );
+    let create = Listing::create_index(
+        "listings",
+        IndexOption {
+            filter: Some(filter),
+            ..Default::default()
+        },
+    );
+    create.validate().unwrap();
+    assert_eq!(
+        to_redis_string(&create),
+        "listings PREFIX 1 p: FILTER @kind == \"product\" && startswith(@__key, \"p:\") && (@__score > 0.5 || @price < 10) SCHEMA kind TEXT price NUMERIC"
+    );
+
+    let create = Listing::create_index("listings", IndexOption::default());
+    create.validate().unwrap();
+    assert_eq!(
+        to_redis_string(&create),
+        "listings PREFIX 1 p: FILTER @kind == \"product\" SCHEMA kind TEXT price NUMERIC"
+    );
+
+    for (filter, err) in [
+        ("@kind == \"product", "unterminated string literal"),
+        ("(@price > 1", "unbalanced parentheses"),
+        ("@price > 1)", "unbalanced parentheses"),
+        ("@ > 1", "`@` without a property name"),
+        ("@title == \"a\"", "unknown property '@title'"),
+        ("@__language == \"en\"", "unknown property '@__language'"),
+    ] {
+        let create = Listing::create_index(
+            "listings",
+            IndexOption {
+                filter: Some(ApplyExpr::raw(filter)),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            create.validate().unwrap_err().to_string(),
+            format!("invalid index filter: {}", err)
+        );
+    }
+
+    let mut conn = MockConnection::new(vec![]);
+    let create = Listing::create_index(
+        "listings",
+        IndexOption {
+            filter: Some(ApplyExpr::raw("@title")),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        rsrs::SearchCommands::ft_create_blocking(&mut conn, &create),
+        Err(rsrs::Error::InvalidFilter(_))
+    ));
+    assert!(conn.commands().is_empty());
+    assert!(matches!(
+        Index::<Listing>::new(
+            "listings",
+            IndexOption {
+                filter: Some(ApplyExpr::raw("@title")),
+                ..Default::default()
+            },
+        ),
+        Err(rsrs::Error::InvalidFilter(_))
+    ));
+}
+
+#[allow(dead_code)]
+#[derive(Document)]
+struct Place {
+    #[field(
+        type = "text",
+        weight = 2.0,
+        no_stem,
+        phonetic = "dm:en",
+        sortable,
+        unf
+    )]
+    name: String,
+    #[field(type = "geo", sortable)]
+    location: String,
+    #[field(type = "geoshape", spherical)]
+    area: String,
+    #[field(type = "vector", hnsw, f32, dim = 4, distance_metric = cosine, m = 16, epsilon = 0.01)]
+    embedding: Vec<f32>,
+}
+
+#[test]
+fn test_schema_kinds() {
+    let create = Place::create_index("places", IndexOption::default());
+    assert_eq!(
+        to_redis_string(&create),
+        "places SCHEMA name TEXT NOSTEM WEIGHT 2 PHONETIC dm:en SORTABLE UNF location GEO SORTABLE area GEOSHAPE SPHERICAL embedding VECTOR HNSW 10 TYPE FLOAT32 DIM 4 DISTANCE_METRIC COSINE M 16 EPSILON 0.01"
+    );
+    assert_eq!(
+        create.schema.iter().map(|x| x.kind.as_str()).collect_vec(),
+        vec!["TEXT", "GEO", "GEOSHAPE", "VECTOR"]
+    );
+    match &create.schema[0].kind {
+        FieldKind::Text(options) => {
+            assert_eq!(options.sortable, Some(Sortable::SortableUnf));
+            assert!(options.no_stem);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let schema = SchemaField {
+        identifier: "price".to_string(),
+        attribute: None,
+        kind: FieldKind::Numeric(NumericOptions {
+            sortable: true,
+            no_index: false,
+        }),
+    };
+    assert_eq!(to_redis_string(&schema), "price NUMERIC SORTABLE");
+}
+
+#[allow(dead_code)]
+#[derive(Document)]
+struct Article {
+    #[field(type = "tag", separator = ";", case_sensitive, with_suffix_trie)]
+    tags: String,
+    #[field(type = "tag", index_empty, index_missing, sortable, unf)]
+    category: String,
+    #[field(type = "tag", no_index)]
+    author: String,
+}
+
+#[test]
+fn test_tag_field() {
+    let create = Article::create_index("articles", IndexOption::default());
+    assert_eq!(
+        to_redis_string(&create),
+        "articles SCHEMA tags TAG SEPARATOR ; CASESENSITIVE WITHSUFFIXTRIE category TAG INDEXEMPTY INDEXMISSING SORTABLE UNF author TAG NOINDEX"
+    );
+
+    let op = Article::op();
+    assert_eq!(
+        to_redis_string(&op.tags.eq("rust").ft_search("articles").unwrap()),
+        "articles @tags:{ rust }"
+    );
+    assert_eq!(
+        to_redis_string(&op.tags.ne("rust").ft_search("articles").unwrap()),
+        "articles -@tags:{ rust }"
+    );
+    assert_eq!(
+        to_redis_string(
+            &op.tags
+                .not_contains(&["a", "b"])
+                .ft_search("articles")
+                .unwrap()
+        ),
+        "articles -@tags:{ a | b }"
+    );
+    assert_eq!(
+        to_redis_string(&op.tags.prefix("ru").ft_search("articles").unwrap()),
+        "articles @tags:{ ru* }"
+    );
+    assert_eq!(
+        to_redis_string(&op.tags.suffix("st").ft_search("articles").unwrap()),
+        "articles @tags:{ *st }"
+    );
+    assert_eq!(
+        to_redis_string(&op.tags.infix("us").ft_search("articles").unwrap()),
+        "articles @tags:{ *us* }"
+    );
+    assert_eq!(
+        to_redis_string(&op.tags.wildcard("r?st*").ft_search("articles").unwrap()),
+        "articles @tags:{ w'r?st*' } DIALECT 2"
+    );
+    assert_eq!(
+        to_redis_string(
+            &op.tags
+                .eq("a")
+                .dialect(1)
+                .and(op.tags.wildcard("r*"))
+                .ft_search("articles")
+                .unwrap()
+        ),
+        "articles @tags:{ a } @tags:{ w'r*' } DIALECT 2"
+    );
+    assert_eq!(
+        to_redis_string(
+            &op.tags
+                .eq("a")
+                .dialect(3)
+                .and(op.tags.wildcard("r*"))
+                .ft_search("articles")
+                .unwrap()
+        ),
+        "articles @tags:{ a } @tags:{ w'r*' } DIALECT 3"
+    );
+    for expr in [
+        op.tags.eq(""),
+        op.tags.contains::<&str>(&[]),
+        op.tags.not_contains(&["a", ""]),
+    ] {
+        assert!(matches!(
+            expr.ft_search("articles"),
+            Err(rsrs::Error::InvalidQuery(_))
+        ));
+    }
+}
+
+#[test]
+fn test_query_attributes() {
+    let op = Place::op();
+    let demo = Demo::op();
+    let search = |expr: Expr| to_redis_string(&expr.ft_search("my_index").unwrap());
+
+    assert_eq!(
+        search(
+            op.name
+                .contains(&["hello", "world"])
+                .with_attributes(QueryAttributes {
+                    weight: Some(2.5),
+                    slop: Some(1),
+                    in_order: Some(true),
+                    phonetic: Some(false),
+                })
+        ),
+        "my_index (@name:(hello|world))=>{ $weight: 2.5; $slop: 1; $inorder: true; $phonetic: false; }"
+    );
+    assert_eq!(
+        search(demo.a1.eq(1).and(
+            !demo.description.eq("aaa").with_attributes(QueryAttributes {
+                weight: Some(0.5),
+                ..Default::default()
+            })
+        )),
+        "my_index @a1:[1 1] -(@description:aaa)=>{ $weight: 0.5; }"
+    );
+    assert_eq!(
+        search(
+            demo.vector32
+                .query(&[1.], 3, None)
+                .and(demo.description.eq("aaa"))
+                .with_attributes(QueryAttributes {
+                    slop: Some(2),
+                    ..Default::default()
+                })
+        ),
+        "my_index ((@description:aaa)=>{ $slop: 2; })=>[KNN 3 @vector32 $vec] PARAMS 2 vec <4Bytes>"
+    );
+    assert_eq!(
+        search(demo.description.eq("aaa").with_attributes(QueryAttributes {
+            phonetic: Some(false),
+            ..Default::default()
+        })),
+        "my_index (@description:aaa)=>{ $phonetic: false; }"
+    );
+
+    let invalid = |expr: Expr| {
+        matches!(
+            expr.ft_search("my_index"),
+            Err(rsrs::Error::InvalidQuery(_))
+        )
+    };
+    assert!(invalid(demo.description.eq("aaa").with_attributes(
+        QueryAttributes {
+            phonetic: Some(true),
+            ..Default::default()
+        }
+    )));
+    assert!(invalid(demo.a1.eq(1).with_attributes(QueryAttributes {
+        slop: Some(1),
+        ..Default::default()
+    })));
+    assert!(invalid(demo.a1.eq(1).with_attributes(QueryAttributes {
+        weight: Some(-1.),
+        ..Default::default()
+    })));
+    let weight = QueryAttributes {
+        weight: Some(2.),
+        ..Default::default()
+    };
+    assert!(invalid(Expr::default().with_attributes(weight.clone())));
+    assert!(invalid(
+        demo.vector32.query(&[1.], 3, None).with_attributes(weight)
+    ));
+}
+
+#[test]
+fn test_escape() {
+    let demo = Demo::op();
+    let article = Article::op();
+    let search = |expr: Expr| to_redis_string(&expr.ft_search("my_index").unwrap());
+
+    assert_eq!(rsrs::escape("a-b @c {d}|e"), "a\\-b\\ \\@c\\ \\{d\\}\\|e");
+    assert_eq!(rsrs::escape("snake_case"), "snake_case");
+    assert_eq!(
+        search(article.tags.contains(&["new york", "a|b"])),
+        "my_index @tags:{ new\\ york | a\\|b }"
+    );
+    assert_eq!(
+        search(article.tags.prefix("c++")),
+        "my_index @tags:{ c\\+\\+* }"
+    );
+    assert_eq!(
+        search(article.tags.wildcard("it's*")),
+        "my_index @tags:{ w'it\\'s*' } DIALECT 2"
+    );
+    assert_eq!(
+        search(demo.description.eq("-@a1:[0 1]")),
+        "my_index @description:\\-\\@a1\\:\\[0\\ 1\\]"
+    );
+    assert_eq!(
+        search(demo.description.phrase("say \"hi\"")),
+        "my_index @description:\"say \\\"hi\\\"\""
+    );
+    assert_eq!(
+        search(demo.description.phrase("state-of-the art | $x (y)")),
+        "my_index @description:\"state\\-of\\-the art \\| \\$x \\(y\\)\""
+    );
+
+    assert_eq!(
+        search(article.tags.eq_param("tag", "new york")),
+        "my_index @tags:{ $tag } PARAMS 2 tag new york DIALECT 2"
+    );
+    assert_eq!(
+        search(demo.description.eq_param("term", "a-b")),
+        "my_index @description:$term PARAMS 2 term a-b DIALECT 2"
+    );
+    for name in ["", "a b", "x}", "$tag"] {
+        assert!(matches!(
+            article.tags.eq_param(name, "x").ft_search("my_index"),
+            Err(rsrs::Error::InvalidQuery(_))
+        ));
+        assert!(matches!(
+            demo.description.eq_param(name, "x").ft_search("my_index"),
+            Err(rsrs::Error::InvalidQuery(_))
+        ));
+        assert!(matches!(
+            demo.vector32
+                .query(&[1.], 3, Some(name))
+                .ft_search("my_index"),
+            Err(rsrs::Error::InvalidQuery(_))
+        ));
+    }
+}
+
+#[test]
+fn test_parameterize() {
+    let demo = Demo::op();
+    let article = Article::op();
+    let place = Place::op();
+    let search = |expr: Expr| to_redis_string(&expr.ft_search("my_index").unwrap());
+
+    assert_eq!(
+        search(
+            demo.a1
+                .in_range(1..10)
+                .and(article.tags.contains(&["a", "b"]))
+                .and(place.location.query(2.5, 48.5, 10, GeoUnit::Km))
+                .parameterize()
+        ),
+        "my_index @a1:[$p0 ($p1] @tags:{ $p2 | $p3 } @location:[$p4 $p5 $p6 km] PARAMS 14 p0 1 p1 10 p2 a p3 b p4 2.5 p5 48.5 p6 10 DIALECT 2"
+    );
+
+    // Names already bound are skipped, and bound values are left alone.
+    assert_eq!(
+        search(
+            article
+                .tags
+                .eq_param("p0", "x")
+                .and(demo.a1.in_range(5..))
+                .parameterize()
+                .parameterize()
+        ),
+        "my_index @tags:{ $p0 } @a1:[$p1 +inf] PARAMS 4 p0 x p1 5 DIALECT 2"
+    );
+
+    assert_eq!(
+        search(
+            demo.vector32
+                .query(&[1.], 3, None)
+                .and(demo.a1.eq(1))
+                .parameterize()
+        ),
+        "my_index (@a1:[$p0 $p0])=>[KNN 3 @vector32 $vec] PARAMS 4 p0 1 vec <4Bytes> DIALECT 2"
+    );
+}
+
+#[test]
+fn test_param_merge() {
+    let demo = Demo::op();
+    let article = Article::op();
+    let search = |expr: Expr| to_redis_string(&expr.ft_search("my_index").unwrap());
+
+    assert_eq!(
+        search(
+            demo.a1
+                .in_range(1..)
+                .parameterize()
+                .and(demo.a2.in_range(2..).parameterize())
+                .or(article.tags.eq_param("p0", "x"))
+        ),
+        "my_index (@a1:[$p0 +inf] @a2:[$p0_1 +inf]) | @tags:{ $p0_2 } PARAMS 6 p0 1 p0_1 2 p0_2 x DIALECT 2"
+    );
+
+    assert_eq!(
+        search(
+            article
+                .tags
+                .eq_param("tag", "x")
+                .and(demo.description.eq_param("tag", "x"))
+        ),
+        "my_index @tags:{ $tag } @description:$tag PARAMS 2 tag x DIALECT 2"
+    );
+
+    assert_eq!(
+        search(
+            demo.description
+                .eq_param("vec", "x")
+                .and(demo.vector32.query(&[1.], 3, None))
+        ),
+        "my_index (@description:$vec)=>[KNN 3 @vector32 $vec_1] PARAMS 4 vec x vec_1 <4Bytes> DIALECT 2"
     );
 }